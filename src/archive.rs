@@ -0,0 +1,1447 @@
+use crate::abort;
+use crate::download::Download;
+use flate2::read::MultiGzDecoder;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::io::Seek;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use tar::EntryType;
+
+/// The format of an asset, which determines how the executables are taken
+/// from it.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, clap::ValueEnum)]
+pub(crate) enum Format {
+    #[value(name = "tar")]
+    #[serde(rename = "tar")]
+    Tar,
+    #[value(name = "tar.gz")]
+    #[serde(rename = "tar.gz")]
+    TarGz,
+    #[value(name = "tar.xz")]
+    #[serde(rename = "tar.xz")]
+    TarXz,
+    #[value(name = "tar.bz2")]
+    #[serde(rename = "tar.bz2")]
+    TarBz2,
+    #[value(name = "tar.zst")]
+    #[serde(rename = "tar.zst")]
+    TarZst,
+    #[value(name = "zip")]
+    #[serde(rename = "zip")]
+    Zip,
+    /// A Debian package, from which the executables are taken without
+    /// running any of its scripts.
+    #[value(name = "deb")]
+    #[serde(rename = "deb")]
+    Deb,
+    /// An RPM package, from which the executables are taken without running
+    /// any of its scripts.
+    #[value(name = "rpm")]
+    #[serde(rename = "rpm")]
+    Rpm,
+    /// A single compressed executable, such as
+    /// `rust-analyzer-x86_64-unknown-linux-gnu.gz`.
+    #[value(name = "gz")]
+    #[serde(rename = "gz")]
+    Gz,
+    #[value(name = "xz")]
+    #[serde(rename = "xz")]
+    Xz,
+    #[value(name = "bz2")]
+    #[serde(rename = "bz2")]
+    Bz2,
+    #[value(name = "zst")]
+    #[serde(rename = "zst")]
+    Zst,
+    /// An uncompressed executable.
+    #[value(name = "binary")]
+    #[serde(rename = "binary")]
+    Binary,
+}
+
+/// The file extensions of the formats.
+///
+/// Compressed tar archives come before the single compressed files, so that
+/// `.tar.gz` is not mistaken for `.gz`.
+static EXTENSIONS: [(&str, Format); 17] = [
+    (".tar", Format::Tar),
+    (".tar.gz", Format::TarGz),
+    (".tgz", Format::TarGz),
+    (".tar.xz", Format::TarXz),
+    (".txz", Format::TarXz),
+    (".tar.bz2", Format::TarBz2),
+    (".tbz", Format::TarBz2),
+    (".tbz2", Format::TarBz2),
+    (".tar.zst", Format::TarZst),
+    (".tzst", Format::TarZst),
+    (".zip", Format::Zip),
+    (".deb", Format::Deb),
+    (".rpm", Format::Rpm),
+    (".gz", Format::Gz),
+    (".xz", Format::Xz),
+    (".bz2", Format::Bz2),
+    (".zst", Format::Zst),
+];
+
+/// The leading bytes of the formats.
+static MAGIC: [(&[u8], Format); 7] = [
+    (b"\x1f\x8b", Format::Gz),
+    (b"\xfd7zXZ\x00", Format::Xz),
+    (b"BZh", Format::Bz2),
+    (b"\x28\xb5\x2f\xfd", Format::Zst),
+    (b"PK\x03\x04", Format::Zip),
+    (b"!<arch>\n", Format::Deb),
+    (b"\xed\xab\xee\xdb", Format::Rpm),
+];
+
+impl Format {
+    /// Return the format based on the file extension of the asset.
+    ///
+    /// Returns `None` if the extension is not known, which usually means an
+    /// uncompressed executable.
+    pub(crate) fn from_name(name: &str) -> Option<Format> {
+        EXTENSIONS
+            .iter()
+            .find(|(extension, _)| name.ends_with(extension))
+            .map(|(_, format)| *format)
+    }
+
+    /// Remove the archive or compression extension from the name, if any.
+    pub(crate) fn strip_extension(name: &str) -> &str {
+        EXTENSIONS
+            .iter()
+            .find_map(|(extension, _)| name.strip_suffix(extension))
+            .unwrap_or(name)
+    }
+
+    /// Return the format based on the leading bytes of the asset.
+    ///
+    /// Compressed streams are decompressed far enough to see whether they
+    /// contain a tar archive. Returns `None` if the bytes are not recognized.
+    fn from_bytes(download: &Download) -> Option<Format> {
+        let mut start = vec![];
+        download.open().take(8).read_to_end(&mut start).ok()?;
+        let format = MAGIC
+            .iter()
+            .find(|(magic, _)| start.starts_with(magic))
+            .map(|(_, format)| *format);
+        match format {
+            Some(format) if format.is_archive() => Some(format),
+            Some(format) => {
+                let reader = decompress(format, download.open()).ok()?;
+                Some(if is_tar(reader) { format.tar() } else { format })
+            }
+            None => is_tar(download.open()).then_some(Format::Tar),
+        }
+    }
+
+    /// Return the format of the asset.
+    ///
+    /// The leading bytes take precedence over the name since release URLs
+    /// do not always end in the right extension.
+    pub(crate) fn detect(download: &Download, name: &str) -> Format {
+        let from_name = Format::from_name(name);
+        let format = match Format::from_bytes(download) {
+            // Tar archives without a ustar header, such as old v7 archives,
+            // are only recognized by their name.
+            Some(format) if from_name == Some(format.tar()) => format.tar(),
+            Some(format) => format,
+            None => from_name.unwrap_or(Format::Binary),
+        };
+        if from_name.is_some_and(|from_name| from_name != format) {
+            tracing::debug!("Detected {format:?} format for {name} from its contents");
+        }
+        format
+    }
+
+    /// The tar archive that is compressed with this format.
+    fn tar(self) -> Format {
+        match self {
+            Format::Gz => Format::TarGz,
+            Format::Xz => Format::TarXz,
+            Format::Bz2 => Format::TarBz2,
+            Format::Zst => Format::TarZst,
+            format => format,
+        }
+    }
+
+    /// Whether the asset contains multiple files from which the executables
+    /// are selected.
+    pub(crate) fn is_archive(self) -> bool {
+        matches!(
+            self,
+            Format::Tar
+                | Format::TarGz
+                | Format::TarXz
+                | Format::TarBz2
+                | Format::TarZst
+                | Format::Zip
+                | Format::Deb
+                | Format::Rpm
+        )
+    }
+}
+
+/// Whether the stream starts with a POSIX or GNU tar header.
+fn is_tar(mut reader: impl Read) -> bool {
+    let mut header = [0; 512];
+    reader.read_exact(&mut header).is_ok() && header[257..262] == *b"ustar"
+}
+
+/// Wrap the reader in a decompressor for the format.
+fn decompress<'a>(format: Format, reader: impl Read + 'a) -> Result<Box<dyn Read + 'a>, String> {
+    let reader: Box<dyn Read + 'a> = match format {
+        Format::TarGz | Format::Gz => Box::new(MultiGzDecoder::new(reader)),
+        Format::TarXz | Format::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+        Format::TarBz2 | Format::Bz2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+        Format::TarZst | Format::Zst => Box::new(
+            zstd::stream::read::Decoder::new(reader)
+                .map_err(|e| format!("Error reading zstd stream: {e}"))?,
+        ),
+        Format::Tar | Format::Binary => Box::new(reader),
+        Format::Zip | Format::Deb | Format::Rpm => {
+            return Err(format!("{format:?} is not a compression format"))
+        }
+    };
+    Ok(reader)
+}
+
+/// Return the path of an entry relative to the archive directory.
+///
+/// Returns `None` for the archive directory itself, such as `./`.
+fn safe_relative(path: &Path) -> Result<Option<PathBuf>, String> {
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => return Err("path contains `..`".to_string()),
+            Component::RootDir | Component::Prefix(_) => return Err("path is absolute".to_string()),
+        }
+    }
+    Ok((!relative.as_os_str().is_empty()).then_some(relative))
+}
+
+/// Return an error if a parent of `relative` is a symlink.
+///
+/// Writing through a symlink that an earlier entry placed would write to
+/// wherever that symlink points.
+fn check_no_symlinks(dest: &Path, relative: &Path) -> Result<(), String> {
+    let mut current = dest.to_path_buf();
+    for component in relative.parent().into_iter().flat_map(|p| p.components()) {
+        current.push(component);
+        let metadata = current.symlink_metadata();
+        if metadata.is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+            return Err(format!(
+                "path goes through the symlink {}",
+                current.strip_prefix(dest).unwrap().display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Rewrite an absolute symlink target in a package relative to the package
+/// root.
+///
+/// Packages are unpacked to `/` by their package manager, so a link such as
+/// `usr/bin/tool -> /usr/lib/tool/tool` points into the package itself.
+fn reroot_symlink_target(relative: &Path, target: &Path) -> PathBuf {
+    if !target.has_root() {
+        return target.to_path_buf();
+    }
+    let mut rerooted = PathBuf::new();
+    for _ in 1..relative.components().count() {
+        rerooted.push("..");
+    }
+    for component in target.components() {
+        match component {
+            Component::RootDir | Component::Prefix(_) => {}
+            component => rerooted.push(component),
+        }
+    }
+    rerooted
+}
+
+/// Return an error if the symlink at `relative` points outside the archive.
+///
+/// `..` is only allowed at the start of the target. Otherwise, a target such
+/// as `link/../..` could climb out via a directory that is itself a symlink.
+fn check_symlink_target(relative: &Path, target: &Path) -> Result<(), String> {
+    let mut depth = relative.components().count() - 1;
+    let mut descended = false;
+    for component in target.components() {
+        match component {
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(format!(
+                    "symlink points to the absolute path {}",
+                    target.display()
+                ))
+            }
+            Component::CurDir => {}
+            Component::ParentDir if descended => {
+                return Err(format!(
+                    "symlink target {} contains `..` after a directory",
+                    target.display()
+                ))
+            }
+            Component::ParentDir if depth == 0 => {
+                return Err(format!(
+                    "symlink points outside the archive to {}",
+                    target.display()
+                ))
+            }
+            Component::ParentDir => depth -= 1,
+            Component::Normal(_) => descended = true,
+        }
+    }
+    Ok(())
+}
+
+/// Return an error if the setuid or setgid bit is set.
+fn check_mode(mode: u32) -> Result<(), String> {
+    if mode & 0o6000 != 0 {
+        return Err(format!("setuid or setgid bit is set in mode {mode:o}"));
+    }
+    Ok(())
+}
+
+fn create_dir(path: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(path).map_err(|e| format!("could not create directory: {e}"))
+}
+
+fn create_parent(path: &Path) -> Result<(), String> {
+    match path.parent() {
+        Some(parent) => create_dir(parent),
+        None => Ok(()),
+    }
+}
+
+/// Remove an existing symlink so that it is replaced instead of followed.
+fn remove_symlink(path: &Path) -> Result<(), String> {
+    let metadata = path.symlink_metadata();
+    if metadata.is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+        std::fs::remove_file(path).map_err(|e| format!("could not replace symlink: {e}"))?;
+    }
+    Ok(())
+}
+
+fn set_mode(path: &Path, mode: Option<u32>) -> Result<(), String> {
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt;
+        let permissions = std::fs::Permissions::from_mode(mode & 0o777);
+        std::fs::set_permissions(path, permissions)
+            .map_err(|e| format!("could not set permissions: {e}"))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (path, mode);
+    Ok(())
+}
+
+fn write_file(path: &Path, reader: &mut dyn Read, mode: Option<u32>) -> Result<(), String> {
+    create_parent(path)?;
+    remove_symlink(path)?;
+    let mut file = File::create(path).map_err(|e| format!("could not create file: {e}"))?;
+    std::io::copy(reader, &mut file).map_err(|e| format!("could not write file: {e}"))?;
+    set_mode(path, mode)
+}
+
+fn create_symlink(dest: &Path, relative: &Path, target: &Path) -> Result<(), String> {
+    let path = dest.join(relative);
+    create_parent(&path)?;
+    remove_symlink(&path)?;
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(target, &path)
+        .map_err(|e| format!("could not create symlink: {e}"))?;
+    #[cfg(not(unix))]
+    tracing::warn!(
+        "Skipping symlink {} to {}",
+        relative.display(),
+        target.display()
+    );
+    Ok(())
+}
+
+fn create_hard_link(dest: &Path, relative: &Path, target: &Path) -> Result<(), String> {
+    check_no_symlinks(dest, target)
+        .map_err(|e| format!("hard link target {}: {e}", target.display()))?;
+    let source = dest.join(target);
+    if !source
+        .symlink_metadata()
+        .is_ok_and(|metadata| metadata.is_file())
+    {
+        return Err(format!(
+            "hard link target {} is not a file in the archive",
+            target.display()
+        ));
+    }
+    let path = dest.join(relative);
+    create_parent(&path)?;
+    remove_symlink(&path)?;
+    std::fs::hard_link(&source, &path).map_err(|e| format!("could not create hard link: {e}"))
+}
+
+/// The kind of an entry in an archive.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum EntryKind {
+    File,
+    Dir,
+    /// A symlink with a target relative to the directory of the link.
+    Symlink(PathBuf),
+    /// A hard link with a target relative to the archive root.
+    HardLink(PathBuf),
+}
+
+/// An archive entry that passed the checks.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ArchiveEntry {
+    /// The path relative to the archive root, without `.` or `..`.
+    pub(crate) path: PathBuf,
+    pub(crate) kind: EntryKind,
+    pub(crate) mode: Option<u32>,
+}
+
+/// Called for every entry with a reader for the contents of the entry.
+type Visit<'a> = dyn FnMut(&ArchiveEntry, &mut dyn Read) -> Result<(), String> + 'a;
+
+/// Check the entry before anything is written.
+///
+/// Absolute symlink targets are only allowed `in_package`, where they are
+/// rewritten relative to the package root. Returns `None` for the archive
+/// root itself.
+fn checked_entry(
+    path: &Path,
+    kind: EntryKind,
+    mode: Option<u32>,
+    in_package: bool,
+) -> Result<Option<ArchiveEntry>, String> {
+    let relative = match safe_relative(path)? {
+        Some(relative) => relative,
+        None => return Ok(None),
+    };
+    if let Some(mode) = mode {
+        check_mode(mode)?;
+    }
+    let kind = match kind {
+        EntryKind::Symlink(target) => {
+            let target = if in_package {
+                reroot_symlink_target(&relative, &target)
+            } else {
+                target
+            };
+            check_symlink_target(&relative, &target)?;
+            EntryKind::Symlink(target)
+        }
+        EntryKind::HardLink(target) => match safe_relative(&target) {
+            Ok(Some(target)) => EntryKind::HardLink(target),
+            Ok(None) => return Err("hard link has an empty target".to_string()),
+            Err(e) => return Err(format!("hard link target {}: {e}", target.display())),
+        },
+        kind => kind,
+    };
+    Ok(Some(ArchiveEntry {
+        path: relative,
+        kind,
+        mode,
+    }))
+}
+
+fn link_name<R: Read>(entry: &tar::Entry<R>) -> Result<PathBuf, String> {
+    match entry.link_name() {
+        Ok(Some(target)) => Ok(target.into_owned()),
+        Ok(None) => Err("link has no target".to_string()),
+        Err(e) => Err(format!("invalid link target: {e}")),
+    }
+}
+
+fn tar_entry<R: Read>(
+    entry: &tar::Entry<R>,
+    path: &Path,
+    in_package: bool,
+) -> Result<Option<ArchiveEntry>, String> {
+    let entry_type = entry.header().entry_type();
+    if entry_type.is_pax_global_extensions()
+        || entry_type.is_pax_local_extensions()
+        || entry_type.is_gnu_longname()
+        || entry_type.is_gnu_longlink()
+    {
+        return Ok(None);
+    }
+    let kind = match entry_type {
+        EntryType::Directory => EntryKind::Dir,
+        EntryType::Regular | EntryType::Continuous => EntryKind::File,
+        EntryType::Symlink => EntryKind::Symlink(link_name(entry)?),
+        EntryType::Link => EntryKind::HardLink(link_name(entry)?),
+        EntryType::Char | EntryType::Block => {
+            return Err("device files are not allowed".to_string())
+        }
+        EntryType::Fifo => return Err("FIFOs are not allowed".to_string()),
+        other => return Err(format!("unsupported entry type {other:?}")),
+    };
+    let mode = entry
+        .header()
+        .mode()
+        .map_err(|e| format!("invalid mode: {e}"))?;
+    checked_entry(path, kind, Some(mode), in_package)
+}
+
+/// Call `visit` for every entry of a tar archive.
+///
+/// Unlike `tar::Archive::unpack`, this rejects absolute paths, `..`, links
+/// that point outside the archive, device files, and setuid bits instead of
+/// skipping or sanitizing them, so that a malicious archive fails loudly.
+fn walk_tar(reader: impl Read, visit: &mut Visit) -> Result<(), String> {
+    walk_tar_entries(reader, false, visit)
+}
+
+fn walk_tar_entries(reader: impl Read, in_package: bool, visit: &mut Visit) -> Result<(), String> {
+    let mut archive = tar::Archive::new(reader);
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("Error reading archive: {e}"))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("Error reading archive: {e}"))?;
+        let path = entry
+            .path()
+            .map_err(|e| format!("Error reading archive: {e}"))?
+            .into_owned();
+        let result = match tar_entry(&entry, &path, in_package) {
+            Ok(Some(checked)) => visit(&checked, &mut entry),
+            Ok(None) => Ok(()),
+            Err(e) => Err(e),
+        };
+        result.map_err(|e| format!("Refusing to unpack {}: {e}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn zip_entry<R: Read>(
+    file: &mut zip::read::ZipFile<'_, R>,
+    name: &str,
+) -> Result<Option<ArchiveEntry>, String> {
+    let mode = file.unix_mode();
+    if let Some(mode) = mode {
+        match mode & 0o170000 {
+            0o020000 | 0o060000 => return Err("device files are not allowed".to_string()),
+            0o010000 => return Err("FIFOs are not allowed".to_string()),
+            0o140000 => return Err("sockets are not allowed".to_string()),
+            _ => {}
+        }
+    }
+    let kind = if file.is_symlink() {
+        let mut target = String::new();
+        file.read_to_string(&mut target)
+            .map_err(|e| format!("invalid symlink target: {e}"))?;
+        EntryKind::Symlink(PathBuf::from(target))
+    } else if file.is_dir() {
+        EntryKind::Dir
+    } else {
+        EntryKind::File
+    };
+    checked_entry(Path::new(name), kind, mode, false)
+}
+
+/// Call `visit` for every entry of a zip archive with the same checks as
+/// [`walk_tar`].
+fn walk_zip(file: impl Read + Seek, visit: &mut Visit) -> Result<(), String> {
+    let mut zip = zip::ZipArchive::new(file).map_err(|e| format!("Error reading archive: {e}"))?;
+    for i in 0..zip.len() {
+        let mut file = zip
+            .by_index(i)
+            .map_err(|e| format!("Error reading archive: {e}"))?;
+        // Zip files created on Windows may use backslashes as separators.
+        let name = file.name().replace('\\', "/");
+        let result = match zip_entry(&mut file, &name) {
+            Ok(Some(checked)) => visit(&checked, &mut file),
+            Ok(None) => Ok(()),
+            Err(e) => Err(e),
+        };
+        result.map_err(|e| format!("Refusing to unpack {name}: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Write the entry below `dest`.
+///
+/// Entries are never written through a symlink that an earlier entry placed.
+fn unpack_entry(dest: &Path, entry: &ArchiveEntry, reader: &mut dyn Read) -> Result<(), String> {
+    check_no_symlinks(dest, &entry.path)?;
+    match &entry.kind {
+        EntryKind::Dir => create_dir(&dest.join(&entry.path)),
+        EntryKind::File => write_file(&dest.join(&entry.path), reader, entry.mode),
+        EntryKind::Symlink(target) => create_symlink(dest, &entry.path, target),
+        EntryKind::HardLink(target) => create_hard_link(dest, &entry.path, target),
+    }
+}
+
+#[cfg(test)]
+fn unpack_tar(reader: impl Read, dest: &Path) -> Result<(), String> {
+    walk_tar(reader, &mut |entry, reader| {
+        unpack_entry(dest, entry, reader)
+    })
+}
+
+#[cfg(test)]
+fn unpack_zip(file: impl Read + Seek, dest: &Path) -> Result<(), String> {
+    walk_zip(file, &mut |entry, reader| unpack_entry(dest, entry, reader))
+}
+
+/// Call `visit` for every entry in the `data.tar.*` member of a Debian
+/// package with the same checks as [`walk_tar`], except that absolute
+/// symlinks are rewritten relative to the package root.
+fn walk_deb(reader: impl Read, visit: &mut Visit) -> Result<(), String> {
+    let mut archive = ar::Archive::new(reader);
+    while let Some(member) = archive.next_entry() {
+        let member = member.map_err(|e| format!("Error reading ar archive: {e}"))?;
+        let identifier = String::from_utf8_lossy(member.header().identifier()).to_string();
+        if !identifier.starts_with("data.tar") {
+            continue;
+        }
+        return match Format::from_name(&identifier) {
+            Some(format) if format.is_archive() => {
+                walk_tar_entries(decompress(format, member)?, true, visit)
+            }
+            _ => Err(format!("unsupported package member {identifier}")),
+        };
+    }
+    Err("no data.tar member in the package".to_string())
+}
+
+fn read_exact(reader: &mut impl Read, buf: &mut [u8], what: &str) -> Result<(), String> {
+    reader
+        .read_exact(buf)
+        .map_err(|e| format!("Error reading {what}: {e}"))
+}
+
+fn skip(reader: &mut impl Read, n: u64, what: &str) -> Result<(), String> {
+    let skipped = std::io::copy(&mut reader.take(n), &mut std::io::sink())
+        .map_err(|e| format!("Error reading {what}: {e}"))?;
+    if skipped != n {
+        return Err(format!("Unexpected end of {what}"));
+    }
+    Ok(())
+}
+
+/// The number of bytes after `len` up to the next multiple of `align`.
+fn padding(len: u64, align: u64) -> u64 {
+    (align - len % align) % align
+}
+
+/// The longest name or symlink target that is read from a cpio archive.
+const CPIO_MAX_NAME: u64 = 4096;
+
+/// The fields of a cpio header that are used.
+struct CpioHeader {
+    ino: u64,
+    mode: u64,
+    nlink: u64,
+    size: u64,
+    name: String,
+}
+
+impl CpioHeader {
+    fn is_file(&self) -> bool {
+        self.mode & 0o170000 == 0o100000
+    }
+
+    fn permissions(&self) -> u32 {
+        (self.mode & 0o7777) as u32
+    }
+}
+
+/// Read the next `newc` header and name of a cpio archive.
+fn cpio_header(reader: &mut impl Read) -> Result<CpioHeader, String> {
+    let mut header = [0; 110];
+    read_exact(reader, &mut header, "cpio archive")?;
+    if &header[..6] != b"070701" && &header[..6] != b"070702" {
+        return Err("Error reading cpio archive: only the newc format is supported".to_string());
+    }
+    let field = |i: usize| {
+        std::str::from_utf8(&header[6 + 8 * i..14 + 8 * i])
+            .ok()
+            .and_then(|field| u64::from_str_radix(field, 16).ok())
+            .ok_or_else(|| "Error reading cpio archive: invalid header".to_string())
+    };
+    let name_size = field(11)?;
+    if name_size == 0 || CPIO_MAX_NAME < name_size {
+        return Err("Error reading cpio archive: invalid name size".to_string());
+    }
+    let mut name = vec![0; name_size as usize];
+    read_exact(reader, &mut name, "cpio archive")?;
+    // The name ends with a NUL byte.
+    name.pop();
+    let name = String::from_utf8(name)
+        .map_err(|_| "Error reading cpio archive: name is not UTF-8".to_string())?;
+    skip(reader, padding(110 + name_size, 4), "cpio archive")?;
+    Ok(CpioHeader {
+        ino: field(0)?,
+        mode: field(1)?,
+        nlink: field(4)?,
+        size: field(6)?,
+        name,
+    })
+}
+
+fn cpio_kind(mode: u64, data: &mut impl Read) -> Result<EntryKind, String> {
+    match mode & 0o170000 {
+        0o100000 => Ok(EntryKind::File),
+        0o040000 => Ok(EntryKind::Dir),
+        0o120000 => {
+            let mut target = String::new();
+            data.take(CPIO_MAX_NAME)
+                .read_to_string(&mut target)
+                .map_err(|e| format!("invalid link target: {e}"))?;
+            Ok(EntryKind::Symlink(PathBuf::from(target)))
+        }
+        0o020000 | 0o060000 => Err("device files are not allowed".to_string()),
+        0o010000 => Err("FIFOs are not allowed".to_string()),
+        other => Err(format!("unsupported entry type {other:o}")),
+    }
+}
+
+fn visit_cpio_entry(
+    path: &Path,
+    kind: EntryKind,
+    mode: u32,
+    reader: &mut dyn Read,
+    visit: &mut Visit,
+) -> Result<(), String> {
+    let result = checked_entry(path, kind, Some(mode), true).and_then(|entry| match entry {
+        Some(entry) => visit(&entry, reader),
+        None => Ok(()),
+    });
+    result.map_err(|e| format!("Refusing to unpack {}: {e}", path.display()))
+}
+
+/// Call `visit` for every entry of a cpio archive in the `newc` format with
+/// the same checks as [`walk_deb`].
+fn walk_cpio(mut reader: impl Read, visit: &mut Visit) -> Result<(), String> {
+    // Hard links share an inode and only the last of them contains the data,
+    // so the others are visited as links to that one.
+    let mut links: HashMap<u64, Vec<(PathBuf, u32)>> = HashMap::new();
+    loop {
+        let header = cpio_header(&mut reader)?;
+        if header.name == "TRAILER!!!" {
+            break;
+        }
+        let path = PathBuf::from(&header.name);
+        let hard_link = header.is_file() && 1 < header.nlink;
+        let mut data = (&mut reader).take(header.size);
+        if hard_link && header.size == 0 {
+            let link = (path, header.permissions());
+            links.entry(header.ino).or_default().push(link);
+            continue;
+        }
+        let kind = cpio_kind(header.mode, &mut data)
+            .map_err(|e| format!("Refusing to unpack {}: {e}", path.display()))?;
+        visit_cpio_entry(&path, kind, header.permissions(), &mut data, visit)?;
+        std::io::copy(&mut data, &mut std::io::sink())
+            .map_err(|e| format!("Error reading cpio archive: {e}"))?;
+        skip(&mut reader, padding(header.size, 4), "cpio archive")?;
+        if hard_link {
+            for (link, mode) in links.remove(&header.ino).unwrap_or_default() {
+                let kind = EntryKind::HardLink(path.clone());
+                visit_cpio_entry(&link, kind, mode, &mut std::io::empty(), visit)?;
+            }
+        }
+    }
+    // Hard links without any data are empty files.
+    for (link, mode) in links.into_values().flatten() {
+        visit_cpio_entry(&link, EntryKind::File, mode, &mut std::io::empty(), visit)?;
+    }
+    Ok(())
+}
+
+/// Return the size of an RPM header structure after its 16-byte intro.
+fn rpm_header_size(reader: &mut impl Read) -> Result<u64, String> {
+    let mut intro = [0; 16];
+    read_exact(reader, &mut intro, "RPM header")?;
+    if intro[..3] != [0x8e, 0xad, 0xe8] {
+        return Err("Error reading RPM header: invalid magic".to_string());
+    }
+    let count = u32::from_be_bytes(intro[8..12].try_into().unwrap());
+    let size = u32::from_be_bytes(intro[12..16].try_into().unwrap());
+    Ok(16 * u64::from(count) + u64::from(size))
+}
+
+/// Call `visit` for every entry in the cpio payload of an RPM package with
+/// the same checks as [`walk_deb`].
+///
+/// The lead and headers are skipped since the payload compression is
+/// detected from its leading bytes.
+fn walk_rpm(mut reader: impl Read, visit: &mut Visit) -> Result<(), String> {
+    let mut lead = [0; 96];
+    read_exact(&mut reader, &mut lead, "RPM lead")?;
+    if !lead.starts_with(b"\xed\xab\xee\xdb") {
+        return Err("Error reading RPM lead: invalid magic".to_string());
+    }
+    // The signature header is padded to a multiple of 8 bytes.
+    let size = rpm_header_size(&mut reader)?;
+    skip(&mut reader, size + padding(size, 8), "RPM signature")?;
+    let size = rpm_header_size(&mut reader)?;
+    skip(&mut reader, size, "RPM header")?;
+
+    let mut start = vec![];
+    (&mut reader)
+        .take(8)
+        .read_to_end(&mut start)
+        .map_err(|e| format!("Error reading RPM payload: {e}"))?;
+    let compression = MAGIC
+        .iter()
+        .find(|(magic, format)| start.starts_with(magic) && !format.is_archive())
+        .map(|(_, format)| *format);
+    let payload = std::io::Cursor::new(start).chain(reader);
+    match compression {
+        Some(format) => walk_cpio(decompress(format, payload)?, visit),
+        None => walk_cpio(payload, visit),
+    }
+}
+
+fn walk(download: &Download, format: Format, visit: &mut Visit) -> Result<(), String> {
+    match format {
+        Format::Zip => walk_zip(download.open(), visit),
+        Format::Deb => walk_deb(download.open(), visit),
+        Format::Rpm => walk_rpm(download.open(), visit),
+        format if format.is_archive() => walk_tar(decompress(format, download.open())?, visit),
+        format => Err(format!("{format:?} is not an archive format")),
+    }
+}
+
+/// Decompress a single compressed executable to `dst`.
+pub(crate) fn decompress_file(
+    download: &Download,
+    name: &str,
+    format: Format,
+    dst: &Path,
+) -> Result<(), String> {
+    let mut reader = decompress(format, download.open())?;
+    File::options()
+        .write(true)
+        .truncate(true)
+        .open(dst)
+        .and_then(|mut file| std::io::copy(&mut reader, &mut file))
+        .map_err(|e| format!("Could not decompress {name}: {e}"))?;
+    Ok(())
+}
+
+/// The entries of an archive, which are used to select the executables
+/// before anything is extracted.
+pub(crate) struct Listing {
+    entries: Vec<ArchiveEntry>,
+}
+
+/// Remove the `..` components that `check_symlink_target` allows.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::Normal(part) => normalized.push(part),
+            _ => {}
+        }
+    }
+    normalized
+}
+
+impl Listing {
+    /// Return the paths directly inside `dir`, where an empty `dir` is the
+    /// archive root.
+    pub(crate) fn children(&self, dir: &Path) -> Vec<PathBuf> {
+        let mut children = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let rest = entry.path.strip_prefix(dir).ok()?;
+                let first = rest.components().next()?;
+                Some(dir.join(first))
+            })
+            .collect::<Vec<_>>();
+        children.sort();
+        children.dedup();
+        children
+    }
+
+    /// Whether the path is a directory, which archives do not always list
+    /// explicitly.
+    pub(crate) fn is_dir(&self, path: &Path) -> bool {
+        self.entries.iter().any(|entry| {
+            if entry.path == path {
+                entry.kind == EntryKind::Dir
+            } else {
+                entry.path.starts_with(path)
+            }
+        })
+    }
+
+    /// Follow links from `path` until reaching a file.
+    pub(crate) fn resolve(&self, path: &Path) -> Result<PathBuf, String> {
+        let mut current = path.to_path_buf();
+        // Bound the number of links to prevent loops.
+        for _ in 0..16 {
+            let entry = self
+                .entries
+                .iter()
+                .rev()
+                .find(|entry| entry.path == current);
+            let entry = match entry {
+                Some(entry) => entry,
+                None => return Err(format!("{} is not in the archive", current.display())),
+            };
+            current = match &entry.kind {
+                EntryKind::File => return Ok(current),
+                EntryKind::Dir => return Err(format!("{} is a directory", current.display())),
+                EntryKind::Symlink(target) => {
+                    normalize(&current.parent().unwrap_or(Path::new("")).join(target))
+                }
+                EntryKind::HardLink(target) => target.clone(),
+            };
+        }
+        Err(format!("too many levels of links at {}", path.display()))
+    }
+}
+
+/// List the entries of the archive.
+///
+/// Aborts if any entry is unsafe, so a malicious archive is rejected even
+/// though only the selected files are extracted.
+pub(crate) fn list_archive(download: &Download, name: &str, format: Format) -> Listing {
+    let mut entries = vec![];
+    let result = walk(download, format, &mut |entry, _| {
+        entries.push(entry.clone());
+        Ok(())
+    });
+    if let Err(e) = result {
+        abort(&format!("Could not unpack {name}: {e}"));
+    }
+    Listing { entries }
+}
+
+/// Extract only the files at the given archive paths to their destinations.
+///
+/// The paths must be resolved to files with [`Listing::resolve`].
+pub(crate) fn extract_files(
+    download: &Download,
+    name: &str,
+    format: Format,
+    files: &[(PathBuf, PathBuf)],
+) -> Result<(), String> {
+    let mut extracted = vec![false; files.len()];
+    let result = walk(download, format, &mut |entry, reader| {
+        if entry.kind != EntryKind::File {
+            return Ok(());
+        }
+        let mut first: Option<&Path> = None;
+        for (i, (src, dst)) in files.iter().enumerate() {
+            if *src != entry.path {
+                continue;
+            }
+            match first {
+                // The same file can be placed under multiple names.
+                Some(first) => std::fs::copy(first, dst).map(|_| ()),
+                None => File::options()
+                    .write(true)
+                    .truncate(true)
+                    .open(dst)
+                    .and_then(|mut file| std::io::copy(reader, &mut file).map(|_| ())),
+            }
+            .map_err(|e| format!("could not write {}: {e}", dst.display()))?;
+            first = Some(dst);
+            extracted[i] = true;
+        }
+        Ok(())
+    });
+    result.map_err(|e| format!("Could not unpack {name}: {e}"))?;
+    match extracted.iter().position(|extracted| !extracted) {
+        Some(i) => Err(format!(
+            "Could not extract {} from {name}",
+            files[i].0.display()
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Unpack the whole archive into the empty directory `dir`.
+///
+/// This is only done with `--keep-archive-contents`, where `dir` is staged
+/// in the same transaction as the executables.
+pub(crate) fn unpack_archive(
+    download: &Download,
+    dir: &Path,
+    name: &str,
+    format: Format,
+) -> Result<(), String> {
+    walk(download, format, &mut |entry, reader| {
+        unpack_entry(dir, entry, reader)
+    })
+    .map_err(|e| format!("could not unpack {name}: {e}"))
+}
+
+/// List the regular files below `dir`, without following symlinks.
+pub(crate) fn files_in_dir(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let file_type = std::fs::symlink_metadata(&path)?.file_type();
+        if file_type.is_dir() {
+            files.extend(files_in_dir(&path)?);
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Build a tar archive without the path checks of `tar::Builder`.
+#[cfg(test)]
+fn crafted_tar(entries: &[(EntryType, &str, &str, u32)]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(vec![]);
+    for (entry_type, name, content, mode) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+        header.set_entry_type(*entry_type);
+        header.set_mode(*mode);
+        let data = if entry_type.is_symlink() || entry_type.is_hard_link() {
+            header.set_link_name_literal(content).unwrap();
+            &[][..]
+        } else {
+            content.as_bytes()
+        };
+        header.set_size(data.len() as u64);
+        header.set_cksum();
+        builder.append(&header, data).unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+#[cfg(test)]
+fn unpack_crafted_tar(entries: &[(EntryType, &str, &str, u32)]) -> Result<(), String> {
+    let dir = tempfile::tempdir().unwrap();
+    unpack_tar(&crafted_tar(entries)[..], dir.path())
+}
+
+#[test]
+fn test_format_from_name() {
+    let cases = [
+        (
+            "typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz",
+            Some(Format::TarGz),
+        ),
+        ("ffmpeg.tgz", Some(Format::TarGz)),
+        (
+            "shellcheck-v0.10.0.linux.x86_64.tar.xz",
+            Some(Format::TarXz),
+        ),
+        ("tool.txz", Some(Format::TarXz)),
+        ("tool.tar.bz2", Some(Format::TarBz2)),
+        ("tool.tbz", Some(Format::TarBz2)),
+        ("tool.tar.zst", Some(Format::TarZst)),
+        (
+            "typos-v1.31.1-x86_64-pc-windows-msvc.zip",
+            Some(Format::Zip),
+        ),
+        (
+            "rust-analyzer-x86_64-unknown-linux-gnu.gz",
+            Some(Format::Gz),
+        ),
+        ("tool.xz", Some(Format::Xz)),
+        ("tool.zst", Some(Format::Zst)),
+        ("ripgrep_14.1.1-1_amd64.deb", Some(Format::Deb)),
+        ("tool-1.0.0-1.x86_64.rpm", Some(Format::Rpm)),
+        ("jq-linux-amd64", None),
+    ];
+    for (name, expected) in cases {
+        assert_eq!(Format::from_name(name), expected, "{name}");
+    }
+    assert!(Format::TarZst.is_archive());
+    assert!(!Format::Gz.is_archive());
+}
+
+#[test]
+fn test_format_detect() {
+    let detect = |data: &[u8], name: &str| {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), data).unwrap();
+        let download = crate::download::copy_to_file(file.path(), &[]).unwrap();
+        Format::detect(&download, name)
+    };
+    let gz = |data: &[u8]| {
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, data).unwrap();
+        encoder.finish().unwrap()
+    };
+    let tar = crafted_tar(&[(EntryType::Regular, "typos", "binary", 0o755)]);
+    let elf: &[u8] = b"\x7fELF\x02\x01\x01";
+
+    assert_eq!(detect(&gz(&tar), "download?format=tgz"), Format::TarGz);
+    assert_eq!(detect(&gz(&tar), "typos.zip"), Format::TarGz);
+    assert_eq!(detect(&gz(elf), "rust-analyzer.gz"), Format::Gz);
+    assert_eq!(
+        detect(&zstd::encode_all(elf, 0).unwrap(), "tool"),
+        Format::Zst
+    );
+    assert_eq!(detect(&tar, "typos"), Format::Tar);
+    assert_eq!(detect(elf, "typos"), Format::Binary);
+    // Without recognizable leading bytes, the name is used.
+    assert_eq!(detect(elf, "typos.tar.xz"), Format::TarXz);
+}
+
+#[test]
+fn test_unpack_tar() {
+    let archive = crafted_tar(&[
+        (EntryType::Directory, "./typos/", "", 0o755),
+        (EntryType::Regular, "./typos/typos", "binary", 0o755),
+        (EntryType::Symlink, "./typos/latest", "typos", 0o777),
+        (EntryType::Symlink, "./typos/doc/readme", "../typos", 0o777),
+        (EntryType::Link, "./typos/copy", "typos/typos", 0o755),
+    ]);
+    let dir = tempfile::tempdir().unwrap();
+    unpack_tar(&archive[..], dir.path()).unwrap();
+    let typos = dir.path().join("typos");
+    assert_eq!(
+        std::fs::read_to_string(typos.join("typos")).unwrap(),
+        "binary"
+    );
+    assert_eq!(
+        std::fs::read_to_string(typos.join("copy")).unwrap(),
+        "binary"
+    );
+    #[cfg(unix)]
+    assert_eq!(
+        std::fs::read_to_string(typos.join("latest")).unwrap(),
+        "binary"
+    );
+}
+
+#[test]
+fn test_listing() {
+    let archive = crafted_tar(&[
+        (
+            EntryType::Regular,
+            "./tool-v1.0.0/README.md",
+            "readme",
+            0o644,
+        ),
+        (
+            EntryType::Regular,
+            "./tool-v1.0.0/bin/tool-1.0",
+            "binary",
+            0o755,
+        ),
+        (
+            EntryType::Symlink,
+            "./tool-v1.0.0/bin/tool",
+            "tool-1.0",
+            0o777,
+        ),
+        (EntryType::Symlink, "./tool-v1.0.0/tool", "bin/tool", 0o777),
+        (
+            EntryType::Link,
+            "./tool-v1.0.0/copy",
+            "tool-v1.0.0/bin/tool-1.0",
+            0o755,
+        ),
+        (EntryType::Symlink, "./tool-v1.0.0/loop", "loop", 0o777),
+    ]);
+    let mut entries = vec![];
+    walk_tar(&archive[..], &mut |entry, _| {
+        entries.push(entry.clone());
+        Ok(())
+    })
+    .unwrap();
+    let listing = Listing { entries };
+    let root = PathBuf::from("tool-v1.0.0");
+    assert_eq!(listing.children(Path::new("")), vec![root.clone()]);
+    assert!(listing.is_dir(&root));
+    assert!(listing.is_dir(&root.join("bin")));
+    assert!(!listing.is_dir(&root.join("tool")));
+    assert_eq!(
+        listing.children(&root),
+        ["README.md", "bin", "copy", "loop", "tool"].map(|name| root.join(name))
+    );
+    let binary = root.join("bin").join("tool-1.0");
+    assert_eq!(listing.resolve(&root.join("tool")), Ok(binary.clone()));
+    assert_eq!(listing.resolve(&root.join("copy")), Ok(binary));
+    assert!(listing.resolve(&root.join("bin")).is_err());
+    assert!(listing.resolve(&root.join("loop")).is_err());
+}
+
+#[test]
+fn test_unpack_tar_rejects_malicious_entries() {
+    let regular = EntryType::Regular;
+    let cases = [
+        (
+            vec![(regular, "/tmp/evil", "x", 0o644)],
+            "Refusing to unpack /tmp/evil: path is absolute",
+        ),
+        (
+            vec![(regular, "../evil", "x", 0o644)],
+            "Refusing to unpack ../evil: path contains `..`",
+        ),
+        (
+            vec![(regular, "a/../../evil", "x", 0o644)],
+            "Refusing to unpack a/../../evil: path contains `..`",
+        ),
+        (
+            vec![(EntryType::Symlink, "passwd", "/etc/passwd", 0o777)],
+            "Refusing to unpack passwd: symlink points to the absolute path /etc/passwd",
+        ),
+        (
+            vec![(EntryType::Symlink, "a/evil", "../../evil", 0o777)],
+            "Refusing to unpack a/evil: symlink points outside the archive to ../../evil",
+        ),
+        (
+            vec![
+                (EntryType::Symlink, "self", ".", 0o777),
+                (EntryType::Symlink, "evil", "self/../evil", 0o777),
+            ],
+            "Refusing to unpack evil: symlink target self/../evil contains `..` after a directory",
+        ),
+        (
+            vec![
+                (EntryType::Symlink, "self", ".", 0o777),
+                (EntryType::Symlink, "self/evil", "../evil", 0o777),
+            ],
+            "Refusing to unpack self/evil: path goes through the symlink self",
+        ),
+        (
+            vec![(EntryType::Link, "evil", "../evil", 0o644)],
+            "Refusing to unpack evil: hard link target ../evil: path contains `..`",
+        ),
+        (
+            vec![(EntryType::Link, "evil", "/etc/passwd", 0o644)],
+            "Refusing to unpack evil: hard link target /etc/passwd: path is absolute",
+        ),
+        (
+            vec![(EntryType::Char, "null", "", 0o666)],
+            "Refusing to unpack null: device files are not allowed",
+        ),
+        (
+            vec![(EntryType::Fifo, "fifo", "", 0o666)],
+            "Refusing to unpack fifo: FIFOs are not allowed",
+        ),
+        (
+            vec![(regular, "typos", "x", 0o4755)],
+            "Refusing to unpack typos: setuid or setgid bit is set in mode 4755",
+        ),
+    ];
+    for (entries, expected) in cases {
+        assert_eq!(unpack_crafted_tar(&entries), Err(expected.to_string()));
+    }
+}
+
+#[test]
+fn test_unpack_zip_rejects_malicious_entries() {
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    type Writer = zip::ZipWriter<std::io::Cursor<Vec<u8>>>;
+    let zip = |add: &dyn Fn(&mut Writer)| {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(vec![]));
+        add(&mut writer);
+        writer.finish().unwrap().into_inner()
+    };
+    let unpack = |archive: Vec<u8>| {
+        let dir = tempfile::tempdir().unwrap();
+        unpack_zip(std::io::Cursor::new(archive), dir.path())
+    };
+    let options = SimpleFileOptions::default();
+
+    let archive = zip(&|writer| {
+        writer.start_file("typos/typos", options).unwrap();
+        writer.write_all(b"binary").unwrap();
+        writer
+            .add_symlink("typos/latest", "typos", options)
+            .unwrap();
+    });
+    assert_eq!(unpack(archive), Ok(()));
+
+    let archive = zip(&|writer| {
+        writer.start_file("../evil", options).unwrap();
+        writer.write_all(b"x").unwrap();
+    });
+    assert_eq!(
+        unpack(archive),
+        Err("Refusing to unpack ../evil: path contains `..`".to_string())
+    );
+
+    let archive = zip(&|writer| {
+        writer.start_file("..\\evil", options).unwrap();
+        writer.write_all(b"x").unwrap();
+    });
+    assert_eq!(
+        unpack(archive),
+        Err("Refusing to unpack ../evil: path contains `..`".to_string())
+    );
+
+    let archive = zip(&|writer| {
+        writer.add_symlink("evil", "/etc/passwd", options).unwrap();
+    });
+    assert_eq!(
+        unpack(archive),
+        Err("Refusing to unpack evil: symlink points to the absolute path /etc/passwd".to_string())
+    );
+}
+
+#[cfg(test)]
+fn crafted_rpm(entries: &[(&str, u32, u32, u32, &str)]) -> Vec<u8> {
+    let mut cpio = vec![];
+    let trailer = ("TRAILER!!!", 0, 0, 1, "");
+    for (name, mode, ino, nlink, data) in entries.iter().chain([&trailer]) {
+        let size = data.len() as u32;
+        let name_size = name.len() as u32 + 1;
+        cpio.extend(b"070701");
+        for field in [*ino, *mode, 0, 0, *nlink, 0, size, 0, 0, 0, 0, name_size, 0] {
+            cpio.extend(format!("{field:08x}").as_bytes());
+        }
+        cpio.extend(name.as_bytes());
+        cpio.push(0);
+        cpio.resize(cpio.len().next_multiple_of(4), 0);
+        cpio.extend(data.as_bytes());
+        cpio.resize(cpio.len().next_multiple_of(4), 0);
+    }
+    let mut rpm = b"\xed\xab\xee\xdb".to_vec();
+    rpm.resize(96, 0);
+    // A signature header with one tag of 5 bytes, padded to 8 bytes.
+    rpm.extend(b"\x8e\xad\xe8\x01\0\0\0\0\0\0\0\x01\0\0\0\x05");
+    rpm.extend([0; 16 + 5 + 3]);
+    rpm.extend(b"\x8e\xad\xe8\x01\0\0\0\0\0\0\0\x01\0\0\0\x04");
+    rpm.extend([0; 16 + 4]);
+    let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+    std::io::Write::write_all(&mut encoder, &cpio).unwrap();
+    rpm.extend(encoder.finish().unwrap());
+    rpm
+}
+
+#[cfg(test)]
+fn walk_crafted_rpm(entries: &[(&str, u32, u32, u32, &str)]) -> Result<Vec<String>, String> {
+    let mut visited = vec![];
+    walk_rpm(&crafted_rpm(entries)[..], &mut |entry, reader| {
+        let mut content = String::new();
+        reader.read_to_string(&mut content).unwrap();
+        visited.push(format!(
+            "{} {:?} {content}",
+            entry.path.display(),
+            entry.kind
+        ));
+        Ok(())
+    })?;
+    Ok(visited)
+}
+
+#[test]
+fn test_walk_rpm() {
+    let visited = walk_crafted_rpm(&[
+        ("./usr", 0o040755, 1, 2, ""),
+        ("./usr/bin", 0o040755, 2, 2, ""),
+        ("./usr/bin/tool-link", 0o100755, 3, 2, ""),
+        ("./usr/bin/tool", 0o100755, 3, 2, "binary"),
+        ("./usr/bin/tool-alias", 0o120777, 4, 1, "tool"),
+    ]);
+    let expected = [
+        "usr Dir ",
+        "usr/bin Dir ",
+        "usr/bin/tool File binary",
+        "usr/bin/tool-link HardLink(\"usr/bin/tool\") ",
+        "usr/bin/tool-alias Symlink(\"tool\") ",
+    ];
+    assert_eq!(
+        visited,
+        Ok(expected.iter().map(|s| s.to_string()).collect())
+    );
+
+    let visited = walk_crafted_rpm(&[("./dev/null", 0o020666, 1, 1, "")]);
+    assert_eq!(
+        visited,
+        Err("Refusing to unpack ./dev/null: device files are not allowed".to_string())
+    );
+    let visited = walk_crafted_rpm(&[("./usr/bin/su", 0o104755, 1, 1, "")]);
+    assert!(visited.unwrap_err().contains("setuid"));
+    let visited = walk_crafted_rpm(&[("../evil", 0o100755, 1, 1, "")]);
+    assert!(visited.unwrap_err().contains("`..`"));
+
+    // Absolute symlinks point into the package once it is installed to `/`.
+    let visited = walk_crafted_rpm(&[("./usr/bin/tool", 0o120777, 1, 1, "/opt/tool/bin/tool")]);
+    assert_eq!(
+        visited,
+        Ok(vec![
+            "usr/bin/tool Symlink(\"../../opt/tool/bin/tool\") ".to_string()
+        ])
+    );
+}
+
+/// The package that `test_install_path_rpm` installs.
+#[test]
+fn test_rpm_fixture() {
+    let rpm = crafted_rpm(&[
+        ("./usr", 0o040755, 1, 2, ""),
+        ("./usr/bin", 0o040755, 2, 2, ""),
+        ("./usr/bin/tool", 0o100755, 3, 1, "tool"),
+        ("./usr/bin/tool-server", 0o100755, 4, 1, "server"),
+        ("./usr/share/doc/tool/README", 0o100644, 5, 1, "readme"),
+    ]);
+    let path = "tests/data/rpm/tool-1.0.0-1.x86_64.rpm";
+    // Set `UPDATE_FIXTURES=1` to write the fixture after changing the entries.
+    if std::env::var_os("UPDATE_FIXTURES").is_some() {
+        std::fs::write(path, &rpm).unwrap();
+    }
+    assert!(
+        std::fs::read(path).unwrap() == rpm,
+        "{path} does not match `crafted_rpm`; run the test with `UPDATE_FIXTURES=1`"
+    );
+}
+
+#[test]
+fn test_walk_deb() {
+    let walk_deb_with = |entries: &[(EntryType, &str, &str, u32)]| {
+        let mut builder = ar::Builder::new(vec![]);
+        let data = crafted_tar(entries);
+        let header = ar::Header::new(b"data.tar".to_vec(), data.len() as u64);
+        builder.append(&header, &data[..]).unwrap();
+        let mut visited = vec![];
+        walk_deb(&builder.into_inner().unwrap()[..], &mut |entry, _| {
+            visited.push(format!("{} {:?}", entry.path.display(), entry.kind));
+            Ok(())
+        })?;
+        Ok::<_, String>(visited)
+    };
+
+    let visited = walk_deb_with(&[
+        (EntryType::Regular, "./usr/lib/tool/tool", "binary", 0o755),
+        (
+            EntryType::Symlink,
+            "./usr/bin/tool",
+            "/usr/lib/tool/tool",
+            0o777,
+        ),
+        (EntryType::Symlink, "./usr/bin/tool-alias", "tool", 0o777),
+    ]);
+    assert_eq!(
+        visited,
+        Ok(vec![
+            "usr/lib/tool/tool File".to_string(),
+            "usr/bin/tool Symlink(\"../../usr/lib/tool/tool\")".to_string(),
+            "usr/bin/tool-alias Symlink(\"tool\")".to_string(),
+        ])
+    );
+
+    // Rewriting the target does not allow climbing out of the package root.
+    let visited = walk_deb_with(&[(
+        EntryType::Symlink,
+        "./usr/bin/evil",
+        "/usr/../../etc/passwd",
+        0o777,
+    )]);
+    assert_eq!(
+        visited,
+        Err("Refusing to unpack ./usr/bin/evil: symlink target ../../usr/../../etc/passwd contains `..` after a directory".to_string())
+    );
+}
@@ -0,0 +1,260 @@
+use crate::abort;
+use crate::download::Download;
+use crate::install::interpret_path;
+use crate::list::format_rows;
+use crate::receipt::read_receipts;
+use crate::receipt::Receipt;
+use crate::sha::HashAlgorithm;
+use crate::CacheArgs;
+use crate::CacheCommand;
+use std::fs::File;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use tempfile::NamedTempFile;
+
+/// The directory in which downloaded assets are stored by SHA-256 hash.
+static DEFAULT_CACHE_DIR: &str = "~/.jas/cache";
+
+pub(crate) fn cache_dir(dir: &Option<String>) -> PathBuf {
+    interpret_path(dir.as_deref().unwrap_or(DEFAULT_CACHE_DIR))
+}
+
+fn is_sha256(name: &str) -> bool {
+    name.len() == 64 && name.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+}
+
+/// Mark the entry as used so that `jas cache prune` keeps it.
+fn touch(path: &Path) {
+    let result = File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+    if let Err(e) = result {
+        tracing::debug!(
+            "Could not update the modified time of {}: {e}",
+            path.display()
+        );
+    }
+}
+
+/// Return the cached asset with the given SHA-256 hash, if any.
+///
+/// The entry is hashed again while copying it, so a corrupted entry is
+/// removed instead of installed.
+pub(crate) fn lookup(dir: &Path, sha: &str, algorithms: &[HashAlgorithm]) -> Option<Download> {
+    let sha = sha.to_lowercase();
+    let path = dir.join(&sha);
+    if !is_sha256(&sha) || !path.is_file() {
+        return None;
+    }
+    match crate::download::copy_to_file(&path, algorithms) {
+        Ok(download) if download.sha256() == sha => {
+            tracing::info!("Using cached asset {}", path.display());
+            touch(&path);
+            Some(download)
+        }
+        Ok(_) => {
+            tracing::warn!("Removing corrupted cache entry {}", path.display());
+            let _ = std::fs::remove_file(&path);
+            None
+        }
+        Err(e) => {
+            tracing::warn!("Could not read cache entry: {e}");
+            None
+        }
+    }
+}
+
+/// Store the asset in the cache.
+///
+/// Failing to store only logs a warning since the asset can be downloaded
+/// again.
+pub(crate) fn store(dir: &Path, download: &Download) {
+    let path = dir.join(download.sha256());
+    if path.is_file() {
+        touch(&path);
+        return;
+    }
+    // Write to a temporary file first so that an interrupted copy is never
+    // mistaken for a complete entry.
+    let result = std::fs::create_dir_all(dir)
+        .and_then(|_| NamedTempFile::new_in(dir))
+        .and_then(|temp| {
+            std::fs::copy(download.path(), temp.path())?;
+            temp.persist(&path).map_err(|e| e.error)?;
+            Ok(())
+        });
+    match result {
+        Ok(()) => tracing::debug!("Stored {} in the cache", path.display()),
+        Err(e) => tracing::warn!("Could not store {} in the cache: {e}", path.display()),
+    }
+}
+
+struct Entry {
+    path: PathBuf,
+    sha: String,
+    size: u64,
+    used: u64,
+}
+
+fn read_entries(dir: &Path) -> Vec<Entry> {
+    if !dir.exists() {
+        return vec![];
+    }
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => abort(&format!(
+            "Could not read the cache at {}: {e}",
+            dir.display()
+        )),
+    };
+    let mut entries = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let sha = entry.file_name().to_str()?.to_string();
+            let metadata = entry.metadata().ok()?;
+            if !is_sha256(&sha) || !metadata.is_file() {
+                return None;
+            }
+            let used = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0);
+            Some(Entry {
+                path: entry.path(),
+                sha,
+                size: metadata.len(),
+                used,
+            })
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.sha.cmp(&b.sha));
+    entries
+}
+
+/// Return the names of the installed tools that were installed from `sha`.
+fn used_by(receipts: &[Receipt], sha: &str) -> Vec<String> {
+    receipts
+        .iter()
+        .filter(|receipt| receipt.sha == sha)
+        .map(|receipt| receipt.name.clone())
+        .collect()
+}
+
+fn format_table(entries: &[Entry], used_by: impl Fn(&str) -> Vec<String>) -> String {
+    let header = ["SHA-256", "SIZE", "LAST USED", "USED BY"];
+    let mut rows = vec![header.iter().map(|s| s.to_string()).collect::<Vec<_>>()];
+    rows.extend(entries.iter().map(|entry| {
+        let names = used_by(&entry.sha);
+        vec![
+            entry.sha.clone(),
+            entry.size.to_string(),
+            crate::time::format_timestamp(entry.used),
+            if names.is_empty() {
+                "-".to_string()
+            } else {
+                names.join(",")
+            },
+        ]
+    }));
+    format_rows(&rows)
+}
+
+fn remove(entry: &Entry) {
+    if let Err(e) = std::fs::remove_file(&entry.path) {
+        abort(&format!("Could not remove {}: {e}", entry.path.display()));
+    }
+    tracing::debug!("Removed {}", entry.path.display());
+}
+
+/// Remove the entries that are not used by any installed tool.
+///
+/// Entries that were used in the last `keep_days` days are kept too.
+fn prune(dir: &Path, keep_days: u64) {
+    let cutoff = crate::time::unix_now().saturating_sub(keep_days * 24 * 60 * 60);
+    let entries = read_entries(dir);
+    let receipts = read_receipts();
+    let mut removed = 0;
+    for entry in &entries {
+        if keep_days != 0 && cutoff <= entry.used {
+            continue;
+        }
+        if used_by(&receipts, &entry.sha).is_empty() {
+            remove(entry);
+            removed += 1;
+        }
+    }
+    tracing::info!(
+        "Removed {removed} of {} entries from {}",
+        entries.len(),
+        dir.display()
+    );
+}
+
+fn clear(dir: &Path) {
+    let entries = read_entries(dir);
+    for entry in &entries {
+        remove(entry);
+    }
+    tracing::info!("Removed {} entries from {}", entries.len(), dir.display());
+}
+
+pub(crate) fn run(args: &CacheArgs) {
+    let dir = cache_dir(&args.cache_dir);
+    match &args.command {
+        CacheCommand::List => {
+            let entries = read_entries(&dir);
+            if entries.is_empty() {
+                tracing::info!("No cached assets in {}", dir.display());
+            } else {
+                let receipts = read_receipts();
+                print!("{}", format_table(&entries, |sha| used_by(&receipts, sha)));
+            }
+        }
+        CacheCommand::Prune { keep_days } => prune(&dir, *keep_days),
+        CacheCommand::Clear => clear(&dir),
+    }
+}
+
+#[test]
+fn test_store_and_lookup() {
+    let source = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(source.path(), b"hello").unwrap();
+    let download = crate::download::copy_to_file(source.path(), &[]).unwrap();
+    let sha = download.sha256();
+    let dir = tempfile::tempdir().unwrap();
+    assert!(lookup(dir.path(), &sha, &[]).is_none());
+
+    store(dir.path(), &download);
+    let cached = lookup(dir.path(), &sha, &[HashAlgorithm::Sha512]).unwrap();
+    assert_eq!(cached.sha256(), sha);
+    assert_eq!(cached.size, 5);
+    assert_eq!(cached.digest(HashAlgorithm::Sha512).len(), 64);
+
+    let entries = read_entries(dir.path());
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].sha, sha);
+
+    std::fs::write(dir.path().join(&sha), b"corrupted").unwrap();
+    assert!(lookup(dir.path(), &sha, &[]).is_none());
+    assert!(!dir.path().join(&sha).exists());
+}
+
+#[test]
+fn test_format_table() {
+    let entries = [Entry {
+        path: PathBuf::from("/cache/x"),
+        sha: "f683c2abeaff70379df7176110100e18150ecd17a4b9785c32908aca11929993".to_string(),
+        size: 1234,
+        used: 1744124706,
+    }];
+    let table = format_table(&entries, |_| vec!["typos".to_string()]);
+    let expected = "\
+SHA-256                                                           SIZE  LAST USED             USED BY
+f683c2abeaff70379df7176110100e18150ecd17a4b9785c32908aca11929993  1234  2025-04-08T15:05:06Z  typos
+";
+    pretty_assertions::assert_eq!(table, expected);
+}
@@ -0,0 +1,156 @@
+use crate::abort;
+use crate::download::Download;
+use serde_json::Value;
+
+fn is_signature(name: &str) -> bool {
+    [".sig", ".asc", ".pem", ".minisig", ".bundle"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
+}
+
+/// Find the checksum file for `asset_name` among the release assets.
+///
+/// Prefers a file for this asset only, such as `<asset>.sha256`, over a file
+/// that contains the checksums of all assets, such as `SHA256SUMS` or
+/// `checksums.txt`.
+fn find_checksum_asset(names: &[&str], asset_name: &str) -> Option<usize> {
+    let per_asset = [
+        format!("{asset_name}.sha256"),
+        format!("{asset_name}.sha256sum"),
+        format!("{asset_name}.sha256.txt"),
+    ];
+    if let Some(index) = names
+        .iter()
+        .position(|name| per_asset.iter().any(|current| current == name))
+    {
+        return Some(index);
+    }
+    names.iter().position(|name| {
+        let name = name.to_lowercase();
+        !is_signature(&name)
+            && (name.contains("checksums") || name.contains("sha256sums"))
+            && !name.contains("sha512")
+    })
+}
+
+fn is_sha256_hex(text: &str) -> bool {
+    text.len() == 64 && text.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Whether `path` in a checksum file refers to `asset_name`.
+fn same_file(path: &str, asset_name: &str) -> bool {
+    let path = path.strip_prefix('*').unwrap_or(path);
+    path.rsplit('/').next() == Some(asset_name)
+}
+
+/// Parse the SHA-256 hash for `asset_name` from a checksum file.
+///
+/// Supports the GNU coreutils format (`<hash>  <file>`), the BSD format
+/// (`SHA256 (<file>) = <hash>`), and files containing only the hash.
+fn parse_checksums(text: &str, asset_name: &str) -> Option<String> {
+    let lines = text
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect::<Vec<_>>();
+    for line in &lines {
+        if let Some(rest) = line.strip_prefix("SHA256 (") {
+            if let Some((path, hash)) = rest.split_once(") = ") {
+                if same_file(path, asset_name) && is_sha256_hex(hash.trim()) {
+                    return Some(hash.trim().to_lowercase());
+                }
+            }
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        if let (Some(hash), Some(path)) = (parts.next(), parts.next()) {
+            if same_file(path, asset_name) && is_sha256_hex(hash) {
+                return Some(hash.to_lowercase());
+            }
+        }
+    }
+    if let [line] = lines.as_slice() {
+        if is_sha256_hex(line) {
+            return Some(line.to_lowercase());
+        }
+    }
+    None
+}
+
+/// Get the SHA-256 hash of `asset_name` from the checksum file in the release.
+pub(crate) fn release_sha(release: &Value, asset_name: &str) -> String {
+    let assets = crate::gh::release_assets(release);
+    let names = crate::gh::asset_names(assets);
+    let index = match find_checksum_asset(&names, asset_name) {
+        Some(index) => index,
+        None => abort(&format!(
+            "Could not find a checksum file in the release. Available assets:\n{}",
+            names.join("\n")
+        )),
+    };
+    let url = assets[index]["browser_download_url"].as_str().unwrap();
+    let body = crate::download::download_file(url);
+    let text = String::from_utf8_lossy(&body);
+    match parse_checksums(&text, asset_name) {
+        Some(sha) => {
+            tracing::info!("Found SHA-256 for {asset_name} in {}", names[index]);
+            sha
+        }
+        None => abort(&format!(
+            "Could not find the SHA-256 for {asset_name} in {}",
+            names[index]
+        )),
+    }
+}
+
+/// Verify the downloaded asset against the hash from the release.
+pub(crate) fn verify_release_sha(download: &Download, expected: &str) {
+    let actual = download.sha256();
+    if actual != expected {
+        abort(&format!(
+            "SHA-256 mismatch with the checksum file in the release: expected\n{expected}, but got\n{actual}"
+        ));
+    }
+}
+
+#[test]
+fn test_find_checksum_asset() {
+    let names = [
+        "typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz",
+        "typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz.sha256",
+        "SHA256SUMS",
+        "SHA256SUMS.asc",
+    ];
+    let asset = "typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz";
+    assert_eq!(find_checksum_asset(&names, asset), Some(1));
+    assert_eq!(find_checksum_asset(&names[2..], asset), Some(0));
+    let names = [
+        "just-1.40.0-x86_64-unknown-linux-musl.tar.gz",
+        "checksums.txt",
+    ];
+    assert_eq!(find_checksum_asset(&names, names[0]), Some(1));
+    assert_eq!(find_checksum_asset(&names[..1], names[0]), None);
+}
+
+#[test]
+fn test_parse_checksums() {
+    let asset = "typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz";
+    let hash = "f683c2abeaff70379df7176110100e18150ecd17a4b9785c32908aca11929993";
+    let other = "a172195e1b1f1e011b3034913d1c87f0bbf0552a096b4ead0e3fa0620f4329cd";
+    let expected = Some(hash.to_string());
+
+    let gnu = format!("{other}  typos-v1.31.1-aarch64-apple-darwin.tar.gz\n{hash}  {asset}\n");
+    assert_eq!(parse_checksums(&gnu, asset), expected);
+    let binary_mode = format!("{hash} *{asset}\n");
+    assert_eq!(parse_checksums(&binary_mode, asset), expected);
+    let with_dir = format!("{hash}  ./dist/{asset}\n");
+    assert_eq!(parse_checksums(&with_dir, asset), expected);
+    let bsd = format!("SHA256 ({asset}) = {}\n", hash.to_uppercase());
+    assert_eq!(parse_checksums(&bsd, asset), expected);
+    let bare = format!("{hash}\n");
+    assert_eq!(parse_checksums(&bare, asset), expected);
+
+    assert_eq!(parse_checksums(&gnu, "typos.zip"), None);
+    let two_bare = format!("{hash}\n{other}\n");
+    assert_eq!(parse_checksums(&two_bare, asset), None);
+}
@@ -0,0 +1,48 @@
+use crate::abort;
+use crate::download::Download;
+use crate::sha::HashAlgorithm;
+use crate::InstallArgs;
+
+/// The file extensions that are used for Sigstore bundles next to the asset.
+static BUNDLE_EXTENSIONS: [&str; 3] = [".sigstore.json", ".sigstore", ".bundle"];
+
+/// Find the bundle next to the asset, such as `<asset>.sigstore.json`.
+fn find_bundle(url: &str, local: bool) -> (String, Vec<u8>) {
+    for extension in BUNDLE_EXTENSIONS {
+        let bundle_url = format!("{url}{extension}");
+        match crate::download::fetch_file_core(&bundle_url, local) {
+            Ok(bundle) => return (bundle_url, bundle),
+            Err(e) => tracing::debug!("No bundle at {bundle_url}: {e}"),
+        }
+    }
+    abort(&format!(
+        "Could not find a Sigstore bundle for {url}; tried {}. Specify one with `--cosign-bundle`. \
+        Note that a detached `.sig` and `.pem` cannot be verified offline since they lack the transparency log entry",
+        BUNDLE_EXTENSIONS.join(", ")
+    ));
+}
+
+/// Verify the downloaded asset against its Sigstore bundle.
+///
+/// The bundle is verified offline against the trust root, so Rekor is not
+/// contacted. The signed entry timestamp in the bundle proves that the
+/// signature was recorded while the short-lived certificate was valid.
+pub(crate) fn verify_cosign(download: &Download, url: &str, args: &InstallArgs, identity: &str) {
+    let issuer = match &args.cosign_issuer {
+        Some(issuer) => issuer,
+        None => abort("`--cosign-identity` requires `--cosign-issuer`"),
+    };
+    let (location, bundle) = match &args.cosign_bundle {
+        Some(location) => (location.clone(), crate::sigstore::read_bundle(location)),
+        None => find_bundle(url, args.path.is_some()),
+    };
+    let root = crate::sigstore::load_trusted_root(&args.trusted_root);
+    let result = crate::sigstore::parse_bundle(&bundle).and_then(|bundle| {
+        let digest = download.digest(HashAlgorithm::Sha256);
+        crate::sigstore::verify_artifact(&bundle, digest, &root, identity, issuer)
+    });
+    match result {
+        Ok(()) => tracing::info!("Verified Sigstore bundle from {location} for {identity}"),
+        Err(e) => abort(&format!("Cosign verification failed for {url}: {e}")),
+    }
+}
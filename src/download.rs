@@ -0,0 +1,290 @@
+use crate::abort;
+use crate::sha::HashAlgorithm;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use tempfile::NamedTempFile;
+
+/// The maximum size of files that are read into memory, such as signatures
+/// and checksum files.
+const SMALL_FILE_LIMIT: u64 = 16 * 1024 * 1024;
+
+/// Whether `--offline` was passed, in which case any network access aborts.
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn set_offline() {
+    OFFLINE.store(true, Ordering::Relaxed);
+}
+
+pub(crate) fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Abort if `--offline` forbids requesting `url`.
+///
+/// This is checked before every request, so that offline mode fails with a
+/// clear error instead of a connection error.
+pub(crate) fn check_online(url: &str) {
+    if is_offline() {
+        abort(&format!(
+            "Cannot request {url} since `--offline` forbids network access"
+        ));
+    }
+}
+
+/// An asset that was streamed to a temporary file while hashing it.
+///
+/// The file is removed when the download is dropped.
+pub(crate) struct Download {
+    file: NamedTempFile,
+    digests: Vec<(HashAlgorithm, Vec<u8>)>,
+    pub(crate) size: u64,
+}
+
+impl Download {
+    pub(crate) fn path(&self) -> &Path {
+        self.file.path()
+    }
+
+    pub(crate) fn open(&self) -> BufReader<File> {
+        match File::open(self.path()) {
+            Ok(file) => BufReader::new(file),
+            Err(e) => abort(&format!(
+                "Could not open the download at {}: {e}",
+                self.path().display()
+            )),
+        }
+    }
+
+    /// Return the digest, which must have been computed during the download.
+    pub(crate) fn digest(&self, algorithm: HashAlgorithm) -> &[u8] {
+        match self
+            .digests
+            .iter()
+            .find(|(current, _)| *current == algorithm)
+        {
+            Some((_, digest)) => digest,
+            None => panic!("{algorithm:?} was not computed during the download"),
+        }
+    }
+
+    /// The SHA-256 hash as hex, which is always computed.
+    pub(crate) fn sha256(&self) -> String {
+        hex::encode(self.digest(HashAlgorithm::Sha256))
+    }
+}
+
+/// Parse a size such as `300M` or `2G` into bytes.
+///
+/// The suffixes are binary, so `1K` is 1024 bytes.
+pub(crate) fn parse_size(text: &str) -> Result<u64, String> {
+    let upper = text.trim().to_uppercase();
+    let upper = upper
+        .strip_suffix("IB")
+        .or_else(|| upper.strip_suffix('B'))
+        .unwrap_or(&upper);
+    let (number, exponent) = match upper.chars().last() {
+        Some('K') => (&upper[..upper.len() - 1], 1),
+        Some('M') => (&upper[..upper.len() - 1], 2),
+        Some('G') => (&upper[..upper.len() - 1], 3),
+        Some('T') => (&upper[..upper.len() - 1], 4),
+        _ => (upper, 0),
+    };
+    let number = number
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| format!("invalid size {text}; expected a size such as 500M or 2G"))?;
+    number
+        .checked_mul(1024u64.pow(exponent))
+        .ok_or_else(|| format!("size {text} is too large"))
+}
+
+fn too_large(url: &str, max_size: u64) -> String {
+    format!(
+        "{url} is larger than the maximum size of {max_size} bytes; increase it with `--max-size`"
+    )
+}
+
+/// Copy `reader` to a temporary file while computing the hashes.
+fn stream_to_file(
+    source: &str,
+    reader: &mut dyn Read,
+    algorithms: &[HashAlgorithm],
+    max_size: Option<u64>,
+) -> Result<Download, String> {
+    let mut file = NamedTempFile::new().map_err(|e| format!("Error creating temp file: {e}"))?;
+    let mut hashers = algorithms
+        .iter()
+        .map(|algorithm| (*algorithm, algorithm.hasher()))
+        .collect::<Vec<_>>();
+    let mut buffer = vec![0; 64 * 1024];
+    let mut size = 0;
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) => return Err(format!("Error reading {source}: {e}")),
+        };
+        size += n as u64;
+        if max_size.is_some_and(|max_size| max_size < size) {
+            return Err(too_large(source, max_size.unwrap()));
+        }
+        let chunk = &buffer[..n];
+        if let Err(e) = file.write_all(chunk) {
+            return Err(format!("Error writing {source} to disk: {e}"));
+        }
+        for (_, hasher) in hashers.iter_mut() {
+            hasher.update(chunk);
+        }
+    }
+    file.flush()
+        .map_err(|e| format!("Error writing {source} to disk: {e}"))?;
+    let digests = hashers
+        .into_iter()
+        .map(|(algorithm, hasher)| (algorithm, hasher.finalize()))
+        .collect();
+    Ok(Download {
+        file,
+        digests,
+        size,
+    })
+}
+
+fn download_to_file_core(
+    url: &str,
+    algorithms: &[HashAlgorithm],
+    max_size: Option<u64>,
+) -> Result<Download, String> {
+    check_online(url);
+    let mut response = match ureq::get(url).call() {
+        Ok(response) => response,
+        Err(e) => return Err(format!("Error downloading {url}: {e}")),
+    };
+    let content_length = response
+        .headers()
+        .get("content-length")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if let (Some(max_size), Some(content_length)) = (max_size, content_length) {
+        if max_size < content_length {
+            return Err(too_large(url, max_size));
+        }
+    }
+    let mut reader = response.body_mut().as_reader();
+    stream_to_file(url, &mut reader, algorithms, max_size)
+}
+
+/// Whether the location is a URL rather than a local path.
+pub(crate) fn is_url(location: &str) -> bool {
+    location.starts_with("https://") || location.starts_with("http://")
+}
+
+pub(crate) fn download_file_core(url: &str) -> Result<Vec<u8>, String> {
+    check_online(url);
+    let mut response = match ureq::get(url).call() {
+        Ok(response) => response,
+        Err(e) => return Err(format!("Error downloading {url}: {e}")),
+    };
+    let body = response.body_mut().with_config().limit(SMALL_FILE_LIMIT);
+    match body.read_to_vec() {
+        Ok(body) => Ok(body),
+        Err(e) => Err(format!("Error reading {url}: {e}")),
+    }
+}
+
+fn with_retries<T>(url: &str, download: impl Fn() -> Result<T, String>) -> T {
+    // Manual retry logic since ureq "3.x has no built-in retries".
+    let retries = 3;
+    for i in 0..retries {
+        match download() {
+            Ok(result) => return result,
+            Err(e) => {
+                if e.contains("timeout") && i < retries - 1 {
+                    let wait = i * i + 1;
+                    tracing::warn!("Timeout downloading {url}, retrying in {wait} seconds");
+                    std::thread::sleep(std::time::Duration::from_secs(wait));
+                } else {
+                    abort(&format!("Error downloading {url}: {e}"));
+                }
+            }
+        }
+    }
+    abort(&format!("Error downloading {url}: timeout"));
+}
+
+/// Download a small file, such as a signature or a checksum file, into memory.
+pub(crate) fn download_file(url: &str) -> Vec<u8> {
+    tracing::info!("Downloading {}", url);
+    with_retries(url, || download_file_core(url))
+}
+
+/// Fetch a small file next to the asset, such as its signature, into memory.
+///
+/// With `local`, the asset was installed with `--path`, so the file is read
+/// from disk instead of downloaded.
+pub(crate) fn fetch_file_core(location: &str, local: bool) -> Result<Vec<u8>, String> {
+    if local {
+        std::fs::read(location).map_err(|e| format!("Error reading {location}: {e}"))
+    } else {
+        download_file_core(location)
+    }
+}
+
+/// Like [`fetch_file_core`], but abort if the file cannot be fetched.
+pub(crate) fn fetch_file(location: &str, local: bool) -> Vec<u8> {
+    if local {
+        tracing::info!("Reading {location}");
+        fetch_file_core(location, local).unwrap_or_else(|e| abort(&e))
+    } else {
+        download_file(location)
+    }
+}
+
+fn with_sha256(algorithms: &[HashAlgorithm]) -> Vec<HashAlgorithm> {
+    let mut algorithms = algorithms.to_vec();
+    if !algorithms.contains(&HashAlgorithm::Sha256) {
+        algorithms.push(HashAlgorithm::Sha256);
+    }
+    algorithms
+}
+
+/// Stream an asset to a temporary file while computing the hashes.
+///
+/// The SHA-256 hash is always computed.
+pub(crate) fn download_to_file(
+    url: &str,
+    algorithms: &[HashAlgorithm],
+    max_size: Option<u64>,
+) -> Download {
+    tracing::info!("Downloading {}", url);
+    let algorithms = with_sha256(algorithms);
+    let download = with_retries(url, || download_to_file_core(url, &algorithms, max_size));
+    tracing::debug!("Downloaded {} bytes", download.size);
+    download
+}
+
+/// Copy a local file to a temporary file while computing the hashes.
+///
+/// The SHA-256 hash is always computed. The copy ensures that the hashes
+/// match the bytes that are installed even if the original file changes.
+pub(crate) fn copy_to_file(path: &Path, algorithms: &[HashAlgorithm]) -> Result<Download, String> {
+    let source = path.display().to_string();
+    let mut file = File::open(path).map_err(|e| format!("Error opening {source}: {e}"))?;
+    stream_to_file(&source, &mut file, &with_sha256(algorithms), None)
+}
+
+#[test]
+fn test_parse_size() {
+    assert_eq!(parse_size("1024"), Ok(1024));
+    assert_eq!(parse_size("300M"), Ok(300 * 1024 * 1024));
+    assert_eq!(parse_size("2G"), Ok(2 * 1024 * 1024 * 1024));
+    assert_eq!(parse_size("2GiB"), Ok(2 * 1024 * 1024 * 1024));
+    assert_eq!(parse_size("512kb"), Ok(512 * 1024));
+    assert!(parse_size("G").is_err());
+    assert!(parse_size("1.5G").is_err());
+    assert!(parse_size("99999999T").is_err());
+}
@@ -0,0 +1,185 @@
+use crate::abort;
+use serde_json::Value;
+
+fn user_agent() -> String {
+    format!("jas/{}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Split `owner/repo@tag` into its parts.
+pub(crate) fn parse_gh(gh: &str) -> (&str, &str, Option<&str>) {
+    let (owner, repo) = match gh.split_once('/') {
+        Some(split) => split,
+        None => abort(&format!(
+            "Expected `--gh` in the form owner/repo@tag, got {gh}"
+        )),
+    };
+    if let Some((repo, tag)) = repo.split_once('@') {
+        (owner, repo, Some(tag))
+    } else {
+        (owner, repo, None)
+    }
+}
+
+#[test]
+fn test_parse_gh() {
+    assert_eq!(
+        parse_gh("crate-ci/typos@v1.31.1"),
+        ("crate-ci", "typos", Some("v1.31.1"))
+    );
+    assert_eq!(
+        parse_gh("rustsec/rustsec@cargo-audit/v0.22.0"),
+        ("rustsec", "rustsec", Some("cargo-audit/v0.22.0"))
+    );
+    assert_eq!(parse_gh("casey/just"), ("casey", "just", None));
+}
+
+fn request(
+    url: &str,
+    gh_token: &Option<String>,
+) -> ureq::RequestBuilder<ureq::typestate::WithoutBody> {
+    crate::download::check_online(url);
+    tracing::debug!("Requesting {}", url);
+    let mut request = ureq::get(url)
+        .header("Accept", "application/vnd.github+json")
+        .header("X-GitHub-Api-Version", "2022-11-28")
+        .header("User-Agent", user_agent());
+    if let Some(token) = gh_token {
+        let token = format!("Bearer {token}");
+        request = request.header("Authorization", token);
+    }
+    request
+}
+
+fn request_json(url: &str, gh_token: &Option<String>) -> Value {
+    let response = request(url, gh_token).call();
+    let mut response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            abort(&format!(
+                "Error requesting asset list. Is the correct tag specified? Error: {e}"
+            ));
+        }
+    };
+    let bytes = match response.body_mut().read_to_vec() {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            abort(&format!("Error reading asset list: {e}"));
+        }
+    };
+    match serde_json::from_slice::<Value>(&bytes) {
+        Ok(body) => body,
+        Err(e) => {
+            abort(&format!("Error parsing asset list: {e}\nGot: {bytes:?}"));
+        }
+    }
+}
+
+/// Get the release with the given tag.
+pub(crate) fn get_release(owner: &str, repo: &str, tag: &str, gh_token: &Option<String>) -> Value {
+    let url = format!("https://api.github.com/repos/{owner}/{repo}/releases/tags/{tag}");
+    request_json(&url, gh_token)
+}
+
+/// Get the latest release.
+///
+/// According to the GitHub Docs, this is "the most recent non-prerelease,
+/// non-draft release".
+pub(crate) fn get_latest_release(owner: &str, repo: &str, gh_token: &Option<String>) -> Value {
+    let url = format!("https://api.github.com/repos/{owner}/{repo}/releases/latest");
+    request_json(&url, gh_token)
+}
+
+/// Get all releases of a repository.
+pub(crate) fn list_releases(owner: &str, repo: &str, gh_token: &Option<String>) -> Vec<Value> {
+    let mut releases = vec![];
+    // GitHub returns at most 100 releases per page.
+    for page in 1..=10 {
+        let url = format!(
+            "https://api.github.com/repos/{owner}/{repo}/releases?per_page=100&page={page}"
+        );
+        let body = request_json(&url, gh_token);
+        let current = match body.as_array() {
+            Some(current) => current.clone(),
+            None => abort(&format!("Unexpected response from GitHub: {body}")),
+        };
+        if current.is_empty() {
+            break;
+        }
+        releases.extend(current);
+    }
+    releases
+}
+
+/// Get the attestations, such as build provenance, for an artifact.
+///
+/// Returns the Sigstore bundles, which are empty if the repository has no
+/// attestations for the digest.
+pub(crate) fn get_attestations(
+    owner: &str,
+    repo: &str,
+    sha: &str,
+    gh_token: &Option<String>,
+) -> Vec<Value> {
+    let url = format!("https://api.github.com/repos/{owner}/{repo}/attestations/sha256:{sha}");
+    let mut response = match request(&url, gh_token).call() {
+        Ok(response) => response,
+        Err(ureq::Error::StatusCode(404)) => return vec![],
+        Err(e) => abort(&format!("Error requesting attestations from {url}: {e}")),
+    };
+    let body = match response.body_mut().read_json::<Value>() {
+        Ok(body) => body,
+        Err(e) => abort(&format!("Error reading attestations from {url}: {e}")),
+    };
+    match body["attestations"].as_array() {
+        Some(attestations) => attestations
+            .iter()
+            .map(|attestation| attestation["bundle"].clone())
+            .collect(),
+        None => abort(&format!("Unexpected response from GitHub: {body}")),
+    }
+}
+
+/// Return the tag of a release.
+pub(crate) fn release_tag(release: &Value) -> &str {
+    match release["tag_name"].as_str() {
+        Some(tag) => tag,
+        None => {
+            abort(&format!("Unexpected response from GitHub: {release}"));
+        }
+    }
+}
+
+/// Return the tag from the download URL of a release asset.
+pub(crate) fn tag_from_asset_url(url: &str) -> Option<&str> {
+    let (_, rest) = url.split_once("/releases/download/")?;
+    let (tag, _) = rest.rsplit_once('/')?;
+    Some(tag)
+}
+
+#[test]
+fn test_tag_from_asset_url() {
+    let url = "https://github.com/crate-ci/typos/releases/download/v1.31.1/typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz";
+    assert_eq!(tag_from_asset_url(url), Some("v1.31.1"));
+    let url =
+        "https://github.com/rustsec/rustsec/releases/download/cargo-audit/v0.22.0/cargo-audit.tgz";
+    assert_eq!(tag_from_asset_url(url), Some("cargo-audit/v0.22.0"));
+    assert_eq!(tag_from_asset_url("https://example.com/typos.tar.gz"), None);
+}
+
+/// Return the assets of a release.
+pub(crate) fn release_assets(release: &Value) -> &[Value] {
+    match release["assets"].as_array() {
+        Some(assets) => assets,
+        None => {
+            abort(&format!("Unexpected response from GitHub: {release}"));
+        }
+    }
+}
+
+/// Return the names of the assets of a release.
+pub(crate) fn asset_names(assets: &[Value]) -> Vec<&str> {
+    assets
+        .iter()
+        .map(|asset| asset["name"].as_str().unwrap())
+        .collect::<Vec<_>>()
+}
@@ -1,20 +1,61 @@
+// The tests were written before clippy flagged `vec!` of string literals.
+#![cfg_attr(test, allow(clippy::useless_vec))]
+
 use crate::abort;
+use std::fmt;
 use std::path::PathBuf;
 
-#[derive(Debug, PartialEq)]
-enum TargetOs {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum TargetOs {
     Linux,
     MacOS,
     Windows,
 }
 
-#[derive(Debug, PartialEq)]
-enum TargetArch {
+impl fmt::Display for TargetOs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TargetOs::Linux => "linux",
+            TargetOs::MacOS => "macos",
+            TargetOs::Windows => "windows",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum TargetArch {
     X86_64,
     Aarch64,
     Arm,
 }
 
+impl fmt::Display for TargetArch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TargetArch::X86_64 => "x86_64",
+            TargetArch::Aarch64 => "aarch64",
+            TargetArch::Arm => "arm",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// The platforms for which an asset can be guessed.
+pub(crate) static PLATFORMS: [(TargetOs, TargetArch); 6] = [
+    (TargetOs::Linux, TargetArch::X86_64),
+    (TargetOs::Linux, TargetArch::Aarch64),
+    (TargetOs::Linux, TargetArch::Arm),
+    (TargetOs::MacOS, TargetArch::X86_64),
+    (TargetOs::MacOS, TargetArch::Aarch64),
+    (TargetOs::Windows, TargetArch::X86_64),
+];
+
+/// The name of a platform such as `linux-x86_64`.
+pub(crate) fn platform_name(target_os: TargetOs, target_arch: TargetArch) -> String {
+    format!("{target_os}-{target_arch}")
+}
+
 fn contains_x86_64(name: &str) -> bool {
     name.contains("x86_64") || name.contains("amd64")
 }
@@ -23,10 +64,32 @@ fn contains_macos(name: &str) -> bool {
     name.contains("macos") || name.contains("darwin")
 }
 
-fn guess_asset_enum(names: &[&str], target_os: TargetOs, target_arch: TargetArch) -> usize {
+fn is_rpm(name: &str) -> bool {
+    name.to_lowercase().ends_with(".rpm")
+}
+
+/// Whether the name of an RPM package matches the architecture.
+///
+/// RPM packages are named `<name>-<version>-<release>.<arch>.rpm` and do not
+/// mention the operating system.
+fn is_rpm_for(name: &str, target_arch: TargetArch) -> bool {
+    let name = &name.to_lowercase();
+    is_rpm(name)
+        && match target_arch {
+            TargetArch::X86_64 => contains_x86_64(name),
+            TargetArch::Aarch64 => name.contains("aarch64") || name.contains("arm64"),
+            TargetArch::Arm => name.contains("arm") && !name.contains("arm64"),
+        }
+}
+
+pub(crate) fn guess_asset_enum(
+    names: &[&str],
+    target_os: TargetOs,
+    target_arch: TargetArch,
+) -> Option<usize> {
     let searcher = |name: &&str| {
         let name = &name.to_lowercase();
-        if name.ends_with(".pkg") {
+        if name.ends_with(".pkg") || is_rpm(name) {
             return false;
         }
         if target_os == TargetOs::Linux && target_arch == TargetArch::X86_64 {
@@ -46,11 +109,16 @@ fn guess_asset_enum(names: &[&str], target_os: TargetOs, target_arch: TargetArch
             std::process::exit(1);
         }
     };
-    names.iter().position(searcher).expect("No asset found")
+    let index = names.iter().position(searcher);
+    // Only fall back to RPM packages if there is nothing else.
+    if index.is_none() && target_os == TargetOs::Linux {
+        return names.iter().position(|name| is_rpm_for(name, target_arch));
+    }
+    index
 }
 
-/// Guess the asset name for the current platform.
-pub fn guess_asset(names: &[&str]) -> usize {
+/// The platform that this binary was compiled for.
+pub(crate) fn host_platform() -> (TargetOs, TargetArch) {
     let target_os = if cfg!(target_os = "linux") {
         TargetOs::Linux
     } else if cfg!(target_os = "macos") {
@@ -71,7 +139,20 @@ pub fn guess_asset(names: &[&str]) -> usize {
         tracing::error!("Unsupported architecture");
         std::process::exit(1);
     };
-    guess_asset_enum(names, target_os, target_arch)
+    (target_os, target_arch)
+}
+
+/// Guess the asset name for the current platform.
+pub fn guess_asset(names: &[&str]) -> usize {
+    let (target_os, target_arch) = host_platform();
+    match guess_asset_enum(names, target_os, target_arch) {
+        Some(index) => index,
+        None => abort(&format!(
+            "Could not guess the asset for {}; specify one with --asset-name. Available assets:\n{}",
+            platform_name(target_os, target_arch),
+            names.join("\n")
+        )),
+    }
 }
 
 #[test]
@@ -84,11 +165,11 @@ fn test_guess_asset_typos() {
         "typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz",
     ];
     let index = guess_asset_enum(&names, TargetOs::MacOS, TargetArch::Aarch64);
-    assert_eq!(index, 0);
+    assert_eq!(index, Some(0));
     let index = guess_asset_enum(&names, TargetOs::Linux, TargetArch::X86_64);
-    assert_eq!(index, 4);
+    assert_eq!(index, Some(4));
     let index = guess_asset_enum(&names, TargetOs::Windows, TargetArch::X86_64);
-    assert_eq!(index, 3);
+    assert_eq!(index, Some(3));
 }
 
 #[test]
@@ -101,11 +182,32 @@ fn test_guess_asset_pandoc() {
         "pandoc-3.6.4-arm64-macOS.zip",
     ];
     let index = guess_asset_enum(&names, TargetOs::Linux, TargetArch::X86_64);
-    assert_eq!(index, 1);
+    assert_eq!(index, Some(1));
     let index = guess_asset_enum(&names, TargetOs::Linux, TargetArch::Arm);
-    assert_eq!(index, 2);
+    assert_eq!(index, Some(2));
     let index = guess_asset_enum(&names, TargetOs::MacOS, TargetArch::Aarch64);
-    assert_eq!(index, 4);
+    assert_eq!(index, Some(4));
+}
+
+#[test]
+fn test_guess_asset_rpm() {
+    let names = vec![
+        "tool-1.0.0-1.aarch64.rpm",
+        "tool-1.0.0-1.x86_64.rpm",
+        "tool-v1.0.0-aarch64-apple-darwin.tar.gz",
+    ];
+    let index = guess_asset_enum(&names, TargetOs::Linux, TargetArch::X86_64);
+    assert_eq!(index, Some(1));
+    let index = guess_asset_enum(&names, TargetOs::Linux, TargetArch::Aarch64);
+    assert_eq!(index, Some(0));
+    let index = guess_asset_enum(&names, TargetOs::MacOS, TargetArch::X86_64);
+    assert_eq!(index, None);
+
+    // Other assets for the platform are preferred.
+    let mut names = names;
+    names.push("tool-v1.0.0-x86_64-unknown-linux-musl.tar.gz");
+    let index = guess_asset_enum(&names, TargetOs::Linux, TargetArch::X86_64);
+    assert_eq!(index, Some(3));
 }
 
 pub(crate) fn guess_executable_in_archive(files: &[PathBuf], name: &str) -> PathBuf {
@@ -190,10 +292,42 @@ fn test_guess_binary_filename_just() {
     assert_eq!(binary, PathBuf::from("just"));
 }
 
+/// The parts of asset names that start the target triple, such as
+/// `x86_64-unknown-linux-gnu` or `linux64`.
+static PLATFORM_PARTS: [&str; 16] = [
+    "x86_64", "x86", "amd64", "aarch64", "arm", "i686", "i386", "linux", "darwin", "macos",
+    "apple", "windows", "win", "unknown", "musl", "gnu",
+];
+
+/// Whether the `-`-separated part of an asset name starts the version or the
+/// target triple, such as `v1.31.1` or `x86_64`.
+fn is_version_or_platform(part: &str) -> bool {
+    let part = part.to_lowercase();
+    let version = part.strip_prefix('v').unwrap_or(&part);
+    version.starts_with(|c: char| c.is_ascii_digit())
+        || PLATFORM_PARTS.iter().any(|prefix| part.starts_with(prefix))
+}
+
+/// Guess the name of the executable from the name of the asset.
+///
+/// The version and target triple are removed, so that
+/// `rust-analyzer-x86_64-unknown-linux-gnu.gz` becomes `rust-analyzer`.
 pub(crate) fn guess_binary_filename_from_url(url: &str) -> String {
     let name = url.split('/').next_back().unwrap();
-    let name = name.split('-').next().unwrap();
-    name.to_string()
+    let name = crate::archive::Format::strip_extension(name);
+    let name = name.strip_suffix(".exe").unwrap_or(name);
+    // Debian packages are named `<package>_<version>_<arch>.deb`.
+    let name = if url.ends_with(".deb") {
+        name.split('_').next().unwrap()
+    } else {
+        name
+    };
+    let parts = name.split('-').collect::<Vec<_>>();
+    let end = parts[1..]
+        .iter()
+        .position(|part| is_version_or_platform(part))
+        .map_or(parts.len(), |i| i + 1);
+    parts[..end].join("-")
 }
 
 #[test]
@@ -201,4 +335,19 @@ fn test_guess_binary_filename_from_url() {
     let url = "https://github.com/typos-ci/typos/releases/download/v1.31.1/typos-v1.31.1-x86_64-apple-darwin.tar.gz";
     let name = guess_binary_filename_from_url(url);
     assert_eq!(name, "typos");
+    let url =
+        "https://github.com/BurntSushi/ripgrep/releases/download/14.1.1/ripgrep_14.1.1-1_amd64.deb";
+    assert_eq!(guess_binary_filename_from_url(url), "ripgrep");
+    let name = "rust-analyzer-x86_64-unknown-linux-gnu.gz";
+    assert_eq!(guess_binary_filename_from_url(name), "rust-analyzer");
+    let name = "ffmpeg-7.1-linux64.tar.xz";
+    assert_eq!(guess_binary_filename_from_url(name), "ffmpeg");
+    assert_eq!(
+        guess_binary_filename_from_url("tool-1.0.0-1.x86_64.rpm"),
+        "tool"
+    );
+    assert_eq!(
+        guess_binary_filename_from_url("cargo-deny.exe"),
+        "cargo-deny"
+    );
 }
@@ -1,23 +1,22 @@
 use crate::abort;
+use crate::archive::Format;
+use crate::archive::Listing;
+use crate::download::Download;
 use crate::guess::guess_asset;
+use crate::lock::LockedAsset;
+use crate::lock::Lockfile;
+use crate::receipt::InstalledFile;
+use crate::receipt::Receipt;
+use crate::sha::HashAlgorithm;
+use crate::sha::Sha256Hash;
+use crate::transaction::Transaction;
 use crate::InstallArgs;
-use flate2::read::GzDecoder;
 use serde_json::Value;
-use std::fs::File;
-use std::io::prelude::*;
 use std::path::Path;
 use std::path::PathBuf;
-use tar::Archive;
-
-fn user_agent() -> String {
-    format!("jas/{}", env!("CARGO_PKG_VERSION"))
-}
 
 fn find_gh_asset(args: &InstallArgs, assets: &[Value]) -> Value {
-    let names = assets
-        .iter()
-        .map(|asset| asset["name"].as_str().unwrap())
-        .collect::<Vec<_>>();
+    let names = crate::gh::asset_names(assets);
     let index = if let Some(name) = &args.asset_name {
         names.iter().position(|current| current == name).unwrap()
     } else {
@@ -27,44 +26,8 @@ fn find_gh_asset(args: &InstallArgs, assets: &[Value]) -> Value {
     asset.clone()
 }
 
-fn get_gh_asset_info(args: &InstallArgs, owner: &str, repo: &str, tag: &str) -> (String, String) {
-    let url = format!("https://api.github.com/repos/{owner}/{repo}/releases/tags/{tag}");
-    tracing::debug!("Requesting asset list from {}", url);
-    let mut request = ureq::get(url)
-        .header("Accept", "application/vnd.github+json")
-        .header("X-GitHub-Api-Version", "2022-11-28")
-        .header("User-Agent", user_agent());
-    if let Some(token) = &args.gh_token {
-        let token = format!("Bearer {token}");
-        request = request.header("Authorization", token);
-    }
-    let response = request.call();
-    let mut response = match response {
-        Ok(response) => response,
-        Err(e) => {
-            abort(&format!(
-                "Error requesting asset list. Is the correct tag specified? Error: {e}"
-            ));
-        }
-    };
-    let bytes = match response.body_mut().read_to_vec() {
-        Ok(bytes) => bytes,
-        Err(e) => {
-            abort(&format!("Error reading asset list: {e}"));
-        }
-    };
-    let body = match serde_json::from_slice::<serde_json::Value>(&bytes) {
-        Ok(body) => body,
-        Err(e) => {
-            abort(&format!("Error parsing asset list: {e}\nGot: {bytes:?}"));
-        }
-    };
-    let assets = match body["assets"].as_array() {
-        Some(assets) => assets,
-        None => {
-            abort(&format!("Unexpected response from GitHub: {body}"));
-        }
-    };
+fn get_gh_asset_info(args: &InstallArgs, release: &Value) -> (String, String) {
+    let assets = crate::gh::release_assets(release);
     let asset = find_gh_asset(args, assets);
     let url = asset["browser_download_url"].as_str().unwrap().to_string();
     let name = asset["name"].as_str().unwrap().to_string();
@@ -79,57 +42,31 @@ pub(crate) fn interpret_path(path: &str) -> PathBuf {
     }
 }
 
-fn verify_sha(body: &[u8], args: &InstallArgs) {
+fn verify_sha(download: &Download, args: &InstallArgs) {
     if let Some(expected) = &args.sha {
-        let actual = crate::sha::Sha256Hash::from_data(body);
-        if expected != &actual {
-            abort(&format!(
-                "SHA-256 mismatch: expected\n{expected}, but got\n{actual}"
-            ));
-        }
+        let pin = crate::sha::parse_pin(expected);
+        crate::sha::verify_pin(&pin, download.digest(pin.algorithm));
     }
 }
 
-fn is_tar_gz(name: &str) -> bool {
-    name.ends_with(".tar.gz") || name.ends_with(".tgz")
-}
-
-/// Unpack a gzipped archive into a directory.
-fn unpack_archive(body: &[u8], dir: &Path, name: &str) -> Option<PathBuf> {
-    let stem = Path::new(name).file_stem();
-    let archive_dir = dir.join(stem.as_ref().unwrap());
-    if is_tar_gz(name) || name.ends_with(".tar.xz") || name.ends_with(".zip") {
-        if archive_dir.exists() {
-            if archive_dir.is_dir() {
-                std::fs::remove_dir_all(&archive_dir).unwrap();
-            } else {
-                std::fs::remove_file(&archive_dir).unwrap();
-            }
-        }
-        std::fs::create_dir_all(&archive_dir).unwrap();
-    }
-    if is_tar_gz(name) {
-        std::fs::create_dir_all(&archive_dir).unwrap();
-        let decompressed = GzDecoder::new(body);
-        let mut archive = Archive::new(decompressed);
-        archive.unpack(&archive_dir).unwrap();
-        tracing::debug!("Unpacked archive into {}", archive_dir.display());
-        Some(archive_dir)
-    } else if name.ends_with(".tar.xz") {
-        let decompressed = xz2::read::XzDecoder::new(body);
-        let mut archive = Archive::new(decompressed);
-        archive.unpack(&archive_dir).unwrap();
-        tracing::debug!("Unpacked archive into {}", archive_dir.display());
-        Some(archive_dir)
-    } else if name.ends_with(".zip") {
-        use zip::unstable::stream::ZipStreamReader;
-        let archive_dir = dir.join(name.strip_suffix(".zip").unwrap());
-        let zip = ZipStreamReader::new(body);
-        zip.extract(&archive_dir).unwrap();
-        tracing::debug!("Unpacked archive into {}", archive_dir.display());
-        Some(archive_dir)
-    } else {
-        None
+/// Verify the signatures of the downloaded asset before anything is written
+/// to the install directory.
+///
+/// With `--path`, `url` is the absolute path of the asset, so the signatures
+/// are read from next to it.
+fn verify_signatures(download: &Download, url: &str, args: &InstallArgs) {
+    let local = args.path.is_some();
+    if let Some(key) = &args.minisign_pubkey {
+        crate::minisign::verify_minisign(download, url, local, key);
+    }
+    if let Some(key) = &args.pgp_key {
+        crate::openpgp::verify_pgp(download, url, local, key);
+    }
+    if let Some(identity) = &args.cosign_identity {
+        crate::cosign::verify_cosign(download, url, args, identity);
+    }
+    if let Some(workflow) = &args.require_provenance {
+        crate::provenance::verify_provenance(download, args, workflow);
     }
 }
 
@@ -178,28 +115,33 @@ fn filter_if_bin(files: &mut Vec<PathBuf>) {
     }
 }
 
-/// Return the files in an archive.
+/// Return the paths of the files in `dir` of the archive.
 ///
 /// Also handles archives with nested directories.
-fn files_in_archive(archive_dir: &Path) -> Vec<PathBuf> {
-    let files = std::fs::read_dir(archive_dir)
-        .unwrap_or_else(|_| abort(&format!("Failed to read directory at {archive_dir:?}")));
-    let mut files = files.map(|file| file.unwrap().path()).collect::<Vec<_>>();
+fn files_in_archive(listing: &Listing, dir: &Path) -> Vec<PathBuf> {
+    let mut files = listing.children(dir);
     filter_if_bin(&mut files);
     // If the archive contains a single dir, read the files in that dir.
-    if files.len() == 1 {
-        let path = &files[0];
-        if path.is_dir() {
-            let path = archive_dir.join(path.file_name().unwrap());
-            files_in_archive(&path)
-        } else {
-            files
-        }
+    if files.len() == 1 && listing.is_dir(&files[0]) {
+        files_in_archive(listing, &files[0].clone())
     } else {
         files
     }
 }
 
+/// Return the directory of the archive in which the executables are searched.
+///
+/// Packages contain a whole file system tree, so their executables are taken
+/// from `usr/bin`.
+fn search_dir(listing: &Listing, format: Format) -> PathBuf {
+    let bin = Path::new("usr/bin");
+    if matches!(format, Format::Deb | Format::Rpm) && listing.is_dir(bin) {
+        bin.to_path_buf()
+    } else {
+        PathBuf::new()
+    }
+}
+
 fn verify_filenames_match(filenames: &[String], executable_filenames: &[String]) {
     if filenames.len() != executable_filenames.len() {
         abort(&format!(
@@ -213,7 +155,8 @@ fn verify_filenames_match(filenames: &[String], executable_filenames: &[String])
 /// Return (src, dst) pairs for each `filename` in `archive_filename`.
 fn handle_filenames(
     dir: &Path,
-    archive_dir: &Path,
+    listing: &Listing,
+    search_dir: &Path,
     args: &InstallArgs,
     filenames: &[String],
 ) -> Vec<(PathBuf, PathBuf)> {
@@ -227,7 +170,7 @@ fn handle_filenames(
         .iter()
         .map(|filename| {
             let filename = add_exe_if_needed(Path::new(filename));
-            let files = files_in_archive(archive_dir);
+            let files = files_in_archive(listing, search_dir);
             let executable = files
                 .iter()
                 .find(|file| file.file_name() == filename.file_name());
@@ -271,12 +214,24 @@ fn make_executable(path: &Path) {
     }
 }
 
-/// Copy the binary from the archive to the target directory.
-fn copy_from_archive(dir: &Path, archive_dir: &Path, args: &InstallArgs, name: &str) {
+/// Extract the binaries from the archive into the transaction.
+///
+/// Only the selected binaries are extracted.
+fn copy_from_archive(
+    download: &Download,
+    archive_name: &str,
+    format: Format,
+    dir: &Path,
+    args: &InstallArgs,
+    name: &str,
+    transaction: &mut Transaction,
+) -> Result<(), String> {
+    let listing = crate::archive::list_archive(download, archive_name, format);
+    let search_dir = search_dir(&listing, format);
     let src_dst = if let Some(filenames) = &args.archive_filename {
-        handle_filenames(dir, archive_dir, args, filenames)
+        handle_filenames(dir, &listing, &search_dir, args, filenames)
     } else {
-        let files = files_in_archive(archive_dir);
+        let files = files_in_archive(&listing, &search_dir);
         let src = crate::guess::guess_executable_in_archive(&files, name);
         let dst = if let Some(executable_filenames) = &args.executable_filename {
             if executable_filenames.len() != 1 {
@@ -291,92 +246,501 @@ fn copy_from_archive(dir: &Path, archive_dir: &Path, args: &InstallArgs, name: &
         let dst = add_exe_if_needed(&dst);
         vec![(src, dst)]
     };
+    let src_dst = src_dst
+        .into_iter()
+        .map(|(src, dst)| match listing.resolve(&src) {
+            Ok(src) => (src, dst),
+            Err(e) => abort(&format!("Could not extract {}: {e}", src.display())),
+        })
+        .collect::<Vec<_>>();
+    let mut src_temp = vec![];
     for (src, dst) in src_dst {
-        let mut reader =
-            File::open(&src).unwrap_or_else(|_| panic!("Failed to open binary at {src:?}"));
-        let mut writer =
-            File::create(&dst).unwrap_or_else(|_| panic!("Failed to create executable at {dst:?}"));
-        std::io::copy(&mut reader, &mut writer).unwrap();
-        tracing::info!("Placed binary at {}", dst.display());
-        make_executable(&dst);
+        src_temp.push((src, transaction.stage(&dst)?));
+    }
+    crate::archive::extract_files(download, archive_name, format, &src_temp)?;
+    for (_, temp) in src_temp {
+        make_executable(&temp);
     }
+    Ok(())
 }
 
-fn download_file_core(url: &str) -> Result<Vec<u8>, String> {
-    let mut response = match ureq::get(url).call() {
-        Ok(response) => response,
-        Err(e) => return Err(format!("Error downloading {url}: {e}")),
-    };
-    let limit_in_megabytes = 300;
-    let limit = limit_in_megabytes * 1024 * 1024;
-    match response.body_mut().with_config().limit(limit).read_to_vec() {
-        Ok(body) => Ok(body),
-        Err(e) => Err(format!("Error reading {url}: {e}")),
+fn copy_file(
+    download: &Download,
+    dir: &Path,
+    output_name: &str,
+    transaction: &mut Transaction,
+) -> Result<(), String> {
+    let path = dir.join(output_name);
+    let temp = transaction.stage(&path)?;
+    if let Err(e) = std::fs::copy(download.path(), &temp) {
+        return Err(format!("Failed to create executable at {path:?}: {e}"));
     }
+    make_executable(&temp);
+    Ok(())
 }
 
-pub(crate) fn download_file(url: &str) -> Vec<u8> {
-    tracing::info!("Downloading {}", url);
-    // Manual retry logic since ureq "3.x has no built-in retries".
-    let retries = 3;
-    for i in 0..retries {
-        match download_file_core(url) {
-            Ok(body) => return body,
-            Err(e) => {
-                if e.contains("timeout") && i < retries - 1 {
-                    let wait = i * i + 1;
-                    tracing::warn!("Timeout downloading {url}, retrying in {wait} seconds");
-                    std::thread::sleep(std::time::Duration::from_secs(wait));
-                } else {
-                    abort(&format!("Error downloading {url}: {e}"));
-                }
+/// Decompress a single compressed executable.
+fn decompress_file(
+    download: &Download,
+    name: &str,
+    format: Format,
+    dir: &Path,
+    output_name: &str,
+    transaction: &mut Transaction,
+) -> Result<(), String> {
+    let temp = transaction.stage(&dir.join(output_name))?;
+    crate::archive::decompress_file(download, name, format, &temp)?;
+    make_executable(&temp);
+    Ok(())
+}
+
+/// Unpack the archive into a staged directory that replaces `archive_dir`.
+fn stage_archive_contents(
+    download: &Download,
+    format: Format,
+    name: &str,
+    archive_dir: &Path,
+    transaction: &mut Transaction,
+) -> Result<(), String> {
+    if transaction.is_staged(archive_dir) {
+        return Err(format!(
+            "{} is both an executable and the directory for the archive contents; specify another `--executable-filename`",
+            archive_dir.display()
+        ));
+    }
+    let staged = transaction.stage_dir(archive_dir)?;
+    crate::archive::unpack_archive(download, &staged, name, format)
+}
+
+/// Write the executables and, if `archive_dir` is given, the archive contents
+/// to temporary files and move them into place together, so that a failure
+/// leaves the previous versions in place.
+///
+/// Returns the paths of the placed executables.
+fn place_executables(
+    download: &Download,
+    format: Format,
+    dir: &Path,
+    args: &InstallArgs,
+    name: &str,
+    output_name: &str,
+    archive_dir: Option<&Path>,
+) -> Vec<PathBuf> {
+    if !format.is_archive()
+        && args
+            .executable_filename
+            .as_ref()
+            .is_some_and(|names| names.len() > 1)
+    {
+        abort("Multiple `executable_filename`s can only be specified for archives");
+    }
+    let mut transaction = Transaction::new();
+    let result = match format {
+        Format::Binary => copy_file(download, dir, output_name, &mut transaction),
+        format if format.is_archive() => copy_from_archive(
+            download,
+            name,
+            format,
+            dir,
+            args,
+            output_name,
+            &mut transaction,
+        ),
+        format => decompress_file(download, name, format, dir, output_name, &mut transaction),
+    };
+    let result = result.and_then(|()| match archive_dir {
+        Some(archive_dir) => {
+            stage_archive_contents(download, format, name, archive_dir, &mut transaction)
+        }
+        None => Ok(()),
+    });
+    let result = match result {
+        Ok(()) => transaction.commit(),
+        Err(e) => {
+            // Remove the temporary files since `abort` may exit without
+            // running destructors.
+            drop(transaction);
+            Err(e)
+        }
+    };
+    match result {
+        Ok(placed) => {
+            let executables = placed
+                .into_iter()
+                .filter(|path| Some(path.as_path()) != archive_dir)
+                .collect::<Vec<_>>();
+            for path in &executables {
+                tracing::info!("Placed binary at {}", path.display());
+            }
+            if let Some(archive_dir) = archive_dir {
+                tracing::debug!("Unpacked archive into {}", archive_dir.display());
             }
+            executables
         }
+        Err(e) => abort(&format!("Could not install {name}: {e}")),
     }
-    abort(&format!("Error downloading {url}: timeout"));
 }
 
-fn copy_file(body: &[u8], dir: &Path, output_name: &str) {
-    let path = dir.join(output_name);
-    let mut file = File::create(&path).unwrap();
-    file.write_all(body).unwrap();
-    make_executable(&path);
+/// Record what was installed so that it can be listed, verified, and uninstalled later.
+fn write_receipt(
+    url: &str,
+    args: &InstallArgs,
+    name: &str,
+    output_name: &str,
+    sha: String,
+    executables: &[PathBuf],
+    archive_dir: Option<PathBuf>,
+) {
+    let (gh, tag) = if let Some(gh) = &args.gh {
+        let (owner, repo, tag) = crate::gh::parse_gh(gh);
+        (
+            Some(format!("{owner}/{repo}")),
+            tag.map(|tag| tag.to_string()),
+        )
+    } else {
+        (None, None)
+    };
+    let installed_file = |path: &PathBuf| InstalledFile {
+        path: std::path::absolute(path).unwrap(),
+        sha: Sha256Hash::from_path(path).to_string(),
+    };
+    let executables = executables.iter().map(installed_file).collect::<Vec<_>>();
+    let archive_dir = archive_dir.map(|dir| std::path::absolute(dir).unwrap());
+    let archive_files = match &archive_dir {
+        Some(dir) => match crate::archive::files_in_dir(dir) {
+            Ok(files) => files.iter().map(installed_file).collect(),
+            Err(e) => abort(&format!("Could not list {}: {e}", dir.display())),
+        },
+        None => vec![],
+    };
+    let dir = std::path::absolute(interpret_path(&args.dir)).unwrap();
+    let receipt = Receipt {
+        name: output_name.to_string(),
+        gh,
+        tag,
+        url: url.to_string(),
+        asset_name: name.to_string(),
+        sha,
+        dir,
+        format: args.format,
+        archive_filename: args.archive_filename.clone(),
+        executable_filename: args.executable_filename.clone(),
+        executables,
+        archive_dir,
+        archive_files,
+        installed_at: crate::time::now(),
+        jas_version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+    crate::receipt::add_receipt(receipt);
+}
+
+/// Download the asset to a temporary file while computing the hashes that
+/// are needed to verify it.
+///
+/// With `--from-file`, the asset is read from that file instead. When `--sha`
+/// is a SHA-256 hash that is in the cache, the cached asset is used instead.
+/// Assets that match a SHA-256 `--sha` are added to the cache, so unpinned
+/// assets never end up in it.
+pub(crate) fn download_asset(url: &str, args: &InstallArgs) -> Download {
+    let mut algorithms = vec![HashAlgorithm::Sha256];
+    let pin = args.sha.as_deref().map(crate::sha::parse_pin);
+    if let Some(pin) = &pin {
+        algorithms.push(pin.algorithm);
+    }
+    let cache_dir = crate::cache::cache_dir(&args.cache_dir);
+    let download = if let Some(path) = &args.from_file {
+        tracing::info!("Reading the asset from {path}");
+        crate::download::copy_to_file(Path::new(path), &algorithms)
+            .unwrap_or_else(|e| abort(&format!("Invalid `--from-file`: {e}")))
+    } else {
+        if let Some(pin) = pin
+            .as_ref()
+            .filter(|pin| pin.algorithm == HashAlgorithm::Sha256)
+        {
+            let sha = hex::encode(&pin.digest);
+            if let Some(download) = crate::cache::lookup(&cache_dir, &sha, &algorithms) {
+                return download;
+            }
+        }
+        if args.offline {
+            abort(&format!(
+                "Cannot download {url} since `--offline` forbids network access and the asset is not in the cache at {}; \
+                pass it with `--from-file`",
+                cache_dir.display()
+            ));
+        }
+        let max_size = args.max_size.as_ref().map(|max_size| {
+            crate::download::parse_size(max_size)
+                .unwrap_or_else(|e| abort(&format!("Invalid `--max-size`: {e}")))
+        });
+        crate::download::download_to_file(url, &algorithms, max_size)
+    };
+    // The cache is keyed by SHA-256, so only a SHA-256 pin vouches for the entry.
+    if pin.is_some_and(|pin| {
+        pin.algorithm == HashAlgorithm::Sha256 && download.digest(pin.algorithm) == pin.digest
+    }) {
+        crate::cache::store(&cache_dir, &download);
+    }
+    download
 }
 
 fn install_core(url: &str, args: &InstallArgs, name: &str, output_name: &str) {
-    let body = download_file(url);
-    verify_sha(&body, args);
+    let download = download_asset(url, args);
+    install_download(&download, url, args, name, output_name);
+}
+
+/// Install an asset that was already downloaded from `url`.
+pub(crate) fn install_download(
+    download: &Download,
+    url: &str,
+    args: &InstallArgs,
+    name: &str,
+    output_name: &str,
+) {
+    verify_sha(download, args);
+    verify_signatures(download, url, args);
+    let sha = download.sha256();
     let dir = interpret_path(&args.dir);
     std::fs::create_dir_all(&dir).unwrap();
-    let archive_dir = unpack_archive(&body, &dir, name);
-    if let Some(archive_dir) = archive_dir {
-        copy_from_archive(&dir, &archive_dir, args, output_name);
-    } else {
-        copy_file(&body, &dir, output_name);
-    }
+    let format = args
+        .format
+        .unwrap_or_else(|| Format::detect(download, name));
+    // A single `--executable-filename` names the executable for every format.
+    let output_name = match args.executable_filename.as_deref() {
+        Some([executable_filename]) => executable_filename,
+        _ => output_name,
+    };
+    let archive_dir = (format.is_archive() && args.keep_archive_contents)
+        .then(|| dir.join(Path::new(name).file_stem().unwrap()));
+    let executables = place_executables(
+        download,
+        format,
+        &dir,
+        args,
+        name,
+        output_name,
+        archive_dir.as_deref(),
+    );
+    write_receipt(url, args, name, output_name, sha, &executables, archive_dir);
     verify_in_path(&dir);
 }
 
+/// Abort if no hash or key is specified while the tag is resolved at install time.
+///
+/// In that case, the hash or key is the only thing that pins the asset.
+fn require_sha(args: &InstallArgs, gh: &str) {
+    let pinned = args.sha.is_some()
+        || args.minisign_pubkey.is_some()
+        || args.pgp_key.is_some()
+        || args.cosign_identity.is_some()
+        || args.require_provenance.is_some();
+    if !pinned {
+        abort(&format!(
+            "Installing {gh} requires `--sha` or a signature key since the tag is resolved at install time; alternatively, specify an exact tag"
+        ));
+    }
+}
+
+fn resolve_version_req(owner: &str, repo: &str, req: &str, gh_token: &Option<String>) -> Value {
+    let parsed = match semver::VersionReq::parse(req) {
+        Ok(parsed) => parsed,
+        Err(e) => abort(&format!("Invalid version requirement {req}: {e}")),
+    };
+    let releases = crate::gh::list_releases(owner, repo, gh_token);
+    match crate::tag::select_release(&releases, &parsed) {
+        Some(release) => release.clone(),
+        None => abort(&format!(
+            "Could not find a release of {owner}/{repo} that matches {req}"
+        )),
+    }
+}
+
+/// Get the release for the tag.
+///
+/// Without a tag or with `latest`, the latest release is used. With a semver
+/// requirement such as `^1.31`, the highest matching release is used.
+pub(crate) fn find_release(
+    owner: &str,
+    repo: &str,
+    tag: Option<&str>,
+    gh_token: &Option<String>,
+) -> Value {
+    match tag {
+        Some(req) if crate::tag::is_version_req(req) => {
+            let release = resolve_version_req(owner, repo, req, gh_token);
+            let tag = crate::gh::release_tag(&release);
+            tracing::info!("Resolved {owner}/{repo}@{req} to {tag}");
+            release
+        }
+        Some(tag) if tag != "latest" => crate::gh::get_release(owner, repo, tag, gh_token),
+        _ => {
+            let release = crate::gh::get_latest_release(owner, repo, gh_token);
+            let tag = crate::gh::release_tag(&release);
+            tracing::info!("Resolved {owner}/{repo} to the latest release {tag}");
+            release
+        }
+    }
+}
+
+/// Get the release for the tag, which requires a pin if the tag is resolved.
+fn resolve_release(args: &InstallArgs, owner: &str, repo: &str, tag: Option<&str>) -> Value {
+    let tag_or_latest = tag.unwrap_or("latest");
+    if tag_or_latest == "latest" || crate::tag::is_version_req(tag_or_latest) {
+        require_sha(args, &format!("{owner}/{repo}@{tag_or_latest}"));
+    }
+    find_release(owner, repo, tag, &args.gh_token)
+}
+
+/// Abort if the locked hash differs from the checksum file in the release.
+fn verify_locked_sha(
+    args: &InstallArgs,
+    lockfile: &str,
+    owner: &str,
+    repo: &str,
+    locked: &LockedAsset,
+) {
+    let tag = match crate::gh::tag_from_asset_url(&locked.url) {
+        Some(tag) => tag,
+        None => abort(&format!(
+            "Cannot use `--sha-from-release` since the locked URL {} is not a GitHub release asset",
+            locked.url
+        )),
+    };
+    let release = crate::gh::get_release(owner, repo, tag, &args.gh_token);
+    let expected = crate::checksums::release_sha(&release, &locked.name);
+    if expected != locked.sha.to_lowercase() {
+        abort(&format!(
+            "SHA-256 of {} in {} does not match the checksum file in the release: expected\n{expected}, but got\n{}",
+            locked.name, lockfile, locked.sha
+        ));
+    }
+}
+
+/// Abort if `sha` from the manifest is not among the locked hashes.
+///
+/// The manifest pins the asset for a single platform, while the lockfile has
+/// the hashes for all platforms, so the hashes only conflict if the lockfile
+/// is for another release or asset.
+fn verify_manifest_sha(sha: &str, path: &str, lockfile: &Lockfile, gh: &str) {
+    let pin = crate::sha::parse_pin(sha);
+    if pin.algorithm != HashAlgorithm::Sha256 {
+        abort(&format!(
+            "Cannot compare `sha` {sha} for {gh} with the SHA-256 hashes in {path}; \
+            pass a SHA-256 hash or remove it since the lockfile pins the asset"
+        ));
+    }
+    let sha = hex::encode(&pin.digest);
+    let assets = crate::lock::locked_assets(lockfile, gh);
+    if !assets.iter().any(|asset| asset.sha.to_lowercase() == sha) {
+        abort(&format!(
+            "`sha` {sha} for {gh} conflicts with the locked hashes in {path}; run `jas lock` again"
+        ));
+    }
+}
+
 fn install_gh(gh: &str, args: &InstallArgs) {
-    let split = gh.split_once('/').unwrap();
-    let owner = split.0;
-    let mut repo = split.1;
-    let tag = if let Some((repo_, tag)) = repo.split_once('@') {
-        repo = repo_;
-        tag
+    let (owner, repo, tag) = crate::gh::parse_gh(gh);
+    let lockfile = args
+        .lockfile
+        .as_deref()
+        .and_then(|path| Some((path, crate::lock::read_lockfile(Path::new(path))?)));
+    let locked = lockfile.as_ref().and_then(|(path, lockfile)| {
+        Some((path, lockfile, crate::lock::locked_asset(lockfile, gh)?))
+    });
+    if let Some((path, lockfile, locked)) = locked {
+        tracing::info!("Using {} for {} from {path}", locked.name, locked.platform);
+        if let Some(asset_name) = &args.asset_name {
+            if asset_name != &locked.name {
+                abort(&format!(
+                    "`--asset-name` {asset_name} conflicts with the locked asset {} in {path}",
+                    locked.name
+                ));
+            }
+        }
+        if args.sha_from_release {
+            verify_locked_sha(args, path, owner, repo, locked);
+        }
+        if let Some(sha) = &args.sha {
+            verify_manifest_sha(sha, path, lockfile, gh);
+        }
+        // The locked hash is for this platform, unlike the `sha` in the manifest.
+        let mut args = args.clone();
+        args.sha = Some(locked.sha.clone());
+        // Record the locked tag in the receipt instead of a requirement.
+        if let Some(tag) = crate::gh::tag_from_asset_url(&locked.url) {
+            args.gh = Some(format!("{owner}/{repo}@{tag}"));
+        }
+        install_core(&locked.url, &args, &locked.name, repo);
+    } else if args.offline {
+        install_gh_offline(gh, args);
     } else {
-        todo!("Missing tag not yet supported")
+        let release = resolve_release(args, owner, repo, tag);
+        let (url, name) = get_gh_asset_info(args, &release);
+        // Record the resolved tag in the receipt.
+        let mut args = args.clone();
+        let tag = crate::gh::release_tag(&release);
+        args.gh = Some(format!("{owner}/{repo}@{tag}"));
+        if args.sha_from_release {
+            let expected = crate::checksums::release_sha(&release, &name);
+            let download = download_asset(&url, &args);
+            crate::checksums::verify_release_sha(&download, &expected);
+            crate::cache::store(&crate::cache::cache_dir(&args.cache_dir), &download);
+            install_download(&download, &url, &args, &name, repo);
+        } else {
+            install_core(&url, &args, &name, repo);
+        }
+    }
+}
+
+/// Install the asset that was recorded in the receipt of an earlier install.
+fn install_gh_offline(gh: &str, args: &InstallArgs) {
+    let (owner, repo, _) = crate::gh::parse_gh(gh);
+    let receipts = crate::receipt::read_receipts();
+    let receipt = match crate::receipt::find_gh_receipt(&receipts, gh) {
+        Some(receipt) => receipt,
+        None => abort(&format!(
+            "Cannot resolve {gh} with `--offline` since it is not in the lockfile and was not installed before; \
+            run `jas lock` and pass `--lockfile`, or install it once without `--offline`"
+        )),
     };
-    let (url, name) = get_gh_asset_info(args, owner, repo, tag);
-    install_core(&url, args, &name, repo);
+    tracing::info!(
+        "Using {} from the receipt of {}",
+        receipt.asset_name,
+        receipt.name
+    );
+    let mut args = args.clone();
+    if let Some(tag) = &receipt.tag {
+        args.gh = Some(format!("{owner}/{repo}@{tag}"));
+    }
+    if args.sha.is_none() {
+        args.sha = Some(receipt.sha.clone());
+    }
+    install_core(&receipt.url, &args, &receipt.asset_name, repo);
 }
 
 fn install_url(url: &str, args: &InstallArgs) {
-    let name = url.split('/').next_back().unwrap();
-    let output_name = crate::guess::guess_binary_filename_from_url(url);
+    // Drop a query such as `?download=1` from the name of the asset.
+    let path = url.split(['?', '#']).next().unwrap();
+    let name = path.split('/').next_back().unwrap();
+    let output_name = crate::guess::guess_binary_filename_from_url(path);
     install_core(url, args, name, &output_name);
 }
 
+fn install_path(path: &str, args: &InstallArgs) {
+    let name = match Path::new(path).file_name().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => abort(&format!("Expected `--path` to point to a file, got {path}")),
+    };
+    let location = match std::path::absolute(path) {
+        Ok(location) => location.display().to_string(),
+        Err(e) => abort(&format!("Invalid `--path` {path}: {e}")),
+    };
+    let output_name = crate::guess::guess_binary_filename_from_url(name);
+    let mut args = args.clone();
+    args.from_file = Some(path.to_string());
+    install_core(&location, &args, name, &output_name);
+}
+
 /// Install a binary.
 pub(crate) fn run(args: &InstallArgs) {
     // Run the check here to error before download.
@@ -386,11 +750,24 @@ pub(crate) fn run(args: &InstallArgs) {
         }
     }
 
+    if args.offline {
+        crate::download::set_offline();
+    }
+
+    if args.sha_from_release && args.gh.is_none() {
+        abort("`--sha-from-release` requires `--gh` to find the checksum file");
+    }
+    if args.sha_from_release && args.offline {
+        abort("`--sha-from-release` cannot be used with `--offline` since the checksum file has to be downloaded");
+    }
+
     if let Some(gh) = &args.gh {
         install_gh(gh, args);
     } else if let Some(url) = &args.url {
         install_url(url, args);
+    } else if let Some(path) = &args.path {
+        install_path(path, args);
     } else {
-        abort("Expected either `--gh` or `--url` to be specified");
+        abort("Expected either `--gh`, `--url`, or `--path` to be specified");
     }
 }
@@ -0,0 +1,82 @@
+use crate::receipt::read_receipts;
+use crate::receipt::Receipt;
+use crate::ListArgs;
+
+fn row(receipt: &Receipt) -> Vec<String> {
+    let source = receipt.gh.clone().unwrap_or(receipt.url.clone());
+    let tag = receipt.tag.clone().unwrap_or("-".to_string());
+    vec![
+        receipt.name.clone(),
+        source,
+        tag,
+        receipt.sha.clone(),
+        receipt.installed_at.clone(),
+        receipt.dir.display().to_string(),
+    ]
+}
+
+/// Format rows as a table with aligned columns.
+///
+/// The first row is the header.
+pub(crate) fn format_rows(rows: &[Vec<String>]) -> String {
+    let widths = (0..rows[0].len())
+        .map(|i| rows.iter().map(|row| row[i].len()).max().unwrap())
+        .collect::<Vec<_>>();
+    rows.iter()
+        .map(|row| {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            format!("{}\n", line.trim_end())
+        })
+        .collect()
+}
+
+fn format_table(receipts: &[Receipt]) -> String {
+    let header = ["NAME", "SOURCE", "TAG", "SHA-256", "INSTALLED", "DIR"];
+    let mut rows = vec![header.iter().map(|s| s.to_string()).collect::<Vec<_>>()];
+    rows.extend(receipts.iter().map(row));
+    format_rows(&rows)
+}
+
+/// Print the installed tools.
+pub(crate) fn run(args: &ListArgs) {
+    let receipts = read_receipts();
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&receipts).unwrap());
+    } else if receipts.is_empty() {
+        tracing::info!("No tools installed");
+    } else {
+        print!("{}", format_table(&receipts));
+    }
+}
+
+#[test]
+fn test_format_table() {
+    let receipt = Receipt {
+        name: "typos".to_string(),
+        gh: Some("crate-ci/typos".to_string()),
+        tag: Some("v1.31.1".to_string()),
+        url: "https://github.com/crate-ci/typos/releases/download/v1.31.1/typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz".to_string(),
+        asset_name: "typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz".to_string(),
+        sha: "f683c2abeaff70379df7176110100e18150ecd17a4b9785c32908aca11929993".to_string(),
+        dir: std::path::PathBuf::from("/home/user/.jas/bin"),
+        format: None,
+        archive_filename: None,
+        executable_filename: None,
+        executables: vec![],
+        archive_dir: None,
+        archive_files: vec![],
+        installed_at: "2025-04-08T15:05:06Z".to_string(),
+        jas_version: "0.3.2".to_string(),
+    };
+    let table = format_table(&[receipt]);
+    let expected = "\
+NAME   SOURCE          TAG      SHA-256                                                           INSTALLED             DIR
+typos  crate-ci/typos  v1.31.1  f683c2abeaff70379df7176110100e18150ecd17a4b9785c32908aca11929993  2025-04-08T15:05:06Z  /home/user/.jas/bin
+";
+    pretty_assertions::assert_eq!(table, expected);
+}
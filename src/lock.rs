@@ -0,0 +1,236 @@
+use crate::abort;
+use crate::guess::guess_asset_enum;
+use crate::guess::host_platform;
+use crate::guess::platform_name;
+use crate::guess::PLATFORMS;
+use crate::manifest::read_manifest;
+use crate::manifest::tool_label;
+use crate::InstallArgs;
+use crate::LockArgs;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The platform name for assets that are the same on all platforms.
+///
+/// This is used when the manifest specifies an `asset-name`.
+static ANY_PLATFORM: &str = "any";
+
+static HEADER: &str = "# This file is generated by `jas lock`. Do not edit it manually.\n\n";
+
+/// The contents of a `jas.lock` file.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Lockfile {
+    #[serde(default, rename = "tool")]
+    pub(crate) tools: Vec<LockedTool>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LockedTool {
+    /// The `--gh` value from the manifest, for example `crate-ci/typos@v1.31.1`.
+    pub(crate) gh: String,
+    #[serde(default, rename = "asset")]
+    pub(crate) assets: Vec<LockedAsset>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LockedAsset {
+    /// The platform such as `linux-x86_64` or `any`.
+    pub(crate) platform: String,
+    pub(crate) name: String,
+    pub(crate) url: String,
+    pub(crate) sha: String,
+}
+
+fn parse_lockfile(text: &str, path: &Path) -> Lockfile {
+    match toml::from_str::<Lockfile>(text) {
+        Ok(lockfile) => lockfile,
+        Err(e) => abort(&format!(
+            "Error parsing lockfile at {}: {e}",
+            path.display()
+        )),
+    }
+}
+
+/// Read the lockfile if it exists.
+pub(crate) fn read_lockfile(path: &Path) -> Option<Lockfile> {
+    if !path.exists() {
+        return None;
+    }
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => abort(&format!(
+            "Could not read lockfile at {}: {e}",
+            path.display()
+        )),
+    };
+    Some(parse_lockfile(&text, path))
+}
+
+fn find_locked_asset<'a>(
+    lockfile: &'a Lockfile,
+    gh: &str,
+    platform: &str,
+) -> Option<&'a LockedAsset> {
+    let tool = lockfile.tools.iter().find(|tool| tool.gh == gh)?;
+    tool.assets
+        .iter()
+        .find(|asset| asset.platform == platform)
+        .or_else(|| {
+            tool.assets
+                .iter()
+                .find(|asset| asset.platform == ANY_PLATFORM)
+        })
+}
+
+/// Return the locked assets for `gh` on all platforms.
+pub(crate) fn locked_assets<'a>(lockfile: &'a Lockfile, gh: &str) -> &'a [LockedAsset] {
+    match lockfile.tools.iter().find(|tool| tool.gh == gh) {
+        Some(tool) => &tool.assets,
+        None => &[],
+    }
+}
+
+/// Return the locked asset for `gh` on the current platform.
+pub(crate) fn locked_asset<'a>(lockfile: &'a Lockfile, gh: &str) -> Option<&'a LockedAsset> {
+    let (target_os, target_arch) = host_platform();
+    find_locked_asset(lockfile, gh, &platform_name(target_os, target_arch))
+}
+
+fn asset_url(asset: &Value) -> String {
+    asset["browser_download_url"].as_str().unwrap().to_string()
+}
+
+/// Download the asset and return its SHA-256 hash.
+///
+/// Multiple platforms can point to the same asset, so the hashes are cached.
+fn hash_asset(url: &str, hashes: &mut HashMap<String, String>) -> String {
+    if let Some(sha) = hashes.get(url) {
+        return sha.clone();
+    }
+    let sha = crate::download::download_to_file(url, &[], None).sha256();
+    hashes.insert(url.to_string(), sha.clone());
+    sha
+}
+
+fn lock_tool(tool: &InstallArgs, gh: &str, gh_token: &Option<String>) -> LockedTool {
+    let (owner, repo, tag) = crate::gh::parse_gh(gh);
+    // Requirements such as `@^1.31` are locked to the release they resolve to
+    // now, until `jas lock` is run again.
+    let release = crate::install::find_release(owner, repo, tag, gh_token);
+    let assets = crate::gh::release_assets(&release);
+    let names = crate::gh::asset_names(assets);
+    let mut hashes = HashMap::new();
+    let mut locked = vec![];
+    if let Some(asset_name) = &tool.asset_name {
+        let index = match names.iter().position(|name| name == asset_name) {
+            Some(index) => index,
+            None => abort(&format!("Could not find asset {asset_name} in {gh}")),
+        };
+        let url = asset_url(&assets[index]);
+        let sha = hash_asset(&url, &mut hashes);
+        locked.push(LockedAsset {
+            platform: ANY_PLATFORM.to_string(),
+            name: asset_name.clone(),
+            url,
+            sha,
+        });
+    } else {
+        for (target_os, target_arch) in PLATFORMS {
+            let platform = platform_name(target_os, target_arch);
+            let index = match guess_asset_enum(&names, target_os, target_arch) {
+                Some(index) => index,
+                None => {
+                    tracing::debug!("No asset found for {platform} in {gh}");
+                    continue;
+                }
+            };
+            let url = asset_url(&assets[index]);
+            let sha = hash_asset(&url, &mut hashes);
+            locked.push(LockedAsset {
+                platform,
+                name: names[index].to_string(),
+                url,
+                sha,
+            });
+        }
+    }
+    if locked.is_empty() {
+        abort(&format!("Could not find any assets for {gh}"));
+    }
+    LockedTool {
+        gh: gh.to_string(),
+        assets: locked,
+    }
+}
+
+fn write_lockfile(lockfile: &Lockfile, path: &Path) {
+    let text = match toml::to_string(lockfile) {
+        Ok(text) => text,
+        Err(e) => abort(&format!("Error serializing lockfile: {e}")),
+    };
+    let text = format!("{HEADER}{text}");
+    if let Err(e) = std::fs::write(path, text) {
+        abort(&format!(
+            "Could not write lockfile at {}: {e}",
+            path.display()
+        ));
+    }
+}
+
+/// Write a lockfile with the assets for all platforms of each tool in the manifest.
+pub(crate) fn run(args: &LockArgs) {
+    let manifest = read_manifest(Path::new(&args.manifest));
+    let mut lockfile = Lockfile::default();
+    for tool in &manifest.tools {
+        if let Some(gh) = &tool.gh {
+            tracing::info!("Locking {gh}");
+            lockfile.tools.push(lock_tool(tool, gh, &args.gh_token));
+        } else {
+            tracing::info!(
+                "Skipping {} since it is not a GitHub release",
+                tool_label(tool)
+            );
+        }
+    }
+    let path = Path::new(&args.lockfile);
+    write_lockfile(&lockfile, path);
+    tracing::info!("Wrote {}", path.display());
+}
+
+#[test]
+fn test_lockfile_roundtrip() {
+    let lockfile = Lockfile {
+        tools: vec![LockedTool {
+            gh: "crate-ci/typos@v1.31.1".to_string(),
+            assets: vec![
+                LockedAsset {
+                    platform: "linux-x86_64".to_string(),
+                    name: "typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz".to_string(),
+                    url: "https://github.com/crate-ci/typos/releases/download/v1.31.1/typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz".to_string(),
+                    sha: "f683c2abeaff70379df7176110100e18150ecd17a4b9785c32908aca11929993".to_string(),
+                },
+                LockedAsset {
+                    platform: "macos-aarch64".to_string(),
+                    name: "typos-v1.31.1-aarch64-apple-darwin.tar.gz".to_string(),
+                    url: "https://github.com/crate-ci/typos/releases/download/v1.31.1/typos-v1.31.1-aarch64-apple-darwin.tar.gz".to_string(),
+                    sha: "a172195e1b1f1e011b3034913d1c87f0bbf0552a096b4ead0e3fa0620f4329cd".to_string(),
+                },
+            ],
+        }],
+    };
+    let text = toml::to_string(&lockfile).unwrap();
+    let parsed = parse_lockfile(&text, Path::new("jas.lock"));
+    assert_eq!(parsed, lockfile);
+
+    let gh = "crate-ci/typos@v1.31.1";
+    let asset = find_locked_asset(&lockfile, gh, "macos-aarch64").unwrap();
+    assert_eq!(asset.name, "typos-v1.31.1-aarch64-apple-darwin.tar.gz");
+    assert!(find_locked_asset(&lockfile, gh, "windows-x86_64").is_none());
+    assert!(find_locked_asset(&lockfile, "crate-ci/typos@v1.31.0", "linux-x86_64").is_none());
+}
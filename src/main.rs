@@ -1,19 +1,48 @@
+mod archive;
+mod cache;
+mod checksums;
+mod cosign;
+mod download;
+mod gh;
 mod guess;
 mod install;
+mod list;
+mod lock;
+mod manifest;
+mod minisign;
+mod openpgp;
+mod outdated;
+mod provenance;
+mod receipt;
 mod sha;
+mod sigstore;
+mod sync;
+mod tag;
+mod time;
+mod transaction;
+mod uninstall;
+mod update;
+mod verify;
 
 use clap::Parser;
+use serde::Deserialize;
 use tracing::subscriber::SetGlobalDefaultError;
 use tracing::Level;
 
 #[derive(Clone, Debug, Parser)]
 pub(crate) struct ShaArgs {
-    /// The file path to compute the SHA-256 hash of
+    /// The file path to compute the hash of
     #[arg(short, long)]
     path: Option<String>,
-    /// The URL to compute the SHA-256 hash of
+    /// The URL to compute the hash of
     #[arg(short, long)]
     url: Option<String>,
+    /// The hash algorithm
+    ///
+    /// Hashes other than SHA-256 are printed with the algorithm as prefix,
+    /// such as `sha512:<hex>`, so that they can be passed to `--sha`.
+    #[arg(long, value_enum, default_value_t = sha::HashAlgorithm::Sha256)]
+    algo: sha::HashAlgorithm,
 }
 
 fn release_mode() -> bool {
@@ -35,11 +64,25 @@ pub(crate) fn abort(message: &str) -> ! {
 
 static DEFAULT_INSTALL_DIR: &str = "~/.jas/bin";
 
-#[derive(Clone, Debug, Parser)]
+static DEFAULT_LOCKFILE: &str = "jas.lock";
+
+fn default_install_dir() -> String {
+    DEFAULT_INSTALL_DIR.to_string()
+}
+
+/// Arguments for installing a single tool.
+///
+/// Also used for the entries in the `jas.toml` manifest, where the keys are
+/// the same as the command line flags.
+#[derive(Clone, Debug, Default, Deserialize, Parser)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
 pub(crate) struct InstallArgs {
     /// The GitHub repository to install from
     ///
-    /// For example, `crate-ci/typos@v1.31.1`.
+    /// For example, `crate-ci/typos@v1.31.1`. Without a tag or with `@latest`,
+    /// the latest release is installed. With a semver requirement such as
+    /// `@^1.31`, the highest matching release is installed. Both require
+    /// `--sha` or a signature key such as `--minisign-pubkey` or `--cosign-identity`.
     #[arg(long)]
     gh: Option<String>,
     /// The GitHub token to use
@@ -69,23 +112,125 @@ pub(crate) struct InstallArgs {
     ///
     /// then this Action will have access to the GITHUB_TOKEN via the `github.token` context.
     #[arg(long, env = "GITHUB_TOKEN", verbatim_doc_comment)]
+    #[serde(skip)]
     gh_token: Option<String>,
     /// The URL to install from
     ///
     /// For example, "github.com/crate-ci/typos/releases/download/v1.31.1/typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz".
     #[arg(long)]
     url: Option<String>,
-    /// The SHA-256 hash of the binary to install
+    /// The local file to install from
+    ///
+    /// The file is verified and unpacked like a downloaded asset. Signatures
+    /// such as `<file>.minisig` are read from next to the file.
+    #[arg(long, conflicts_with_all = ["gh", "url"])]
+    path: Option<String>,
+    /// The hash of the binary to install
+    ///
+    /// Either a SHA-256 hex digest, a digest prefixed with the algorithm such
+    /// as `sha512:<hex>` or `blake3:<hex>`, or a Subresource Integrity digest
+    /// such as `sha256-<base64>`.
     ///
     /// [default: no verification if no hash is provided]
     #[arg(long)]
     sha: Option<String>,
+    /// Verify the SHA-256 hash against the checksum file in the GitHub release
+    ///
+    /// Looks for a file such as `<asset>.sha256`, `SHA256SUMS`, or
+    /// `checksums.txt` in the release. Can be combined with `--sha`. When the
+    /// asset is read from the lockfile, the locked hash is compared against
+    /// the checksum file.
+    #[arg(long)]
+    sha_from_release: bool,
+    /// The minisign public key to verify the asset with
+    ///
+    /// Either the base64 key such as `RWQf6LRC...` or the path to a
+    /// `minisign.pub` file. The signature is downloaded from `<asset>.minisig`.
+    #[arg(long)]
+    minisign_pubkey: Option<String>,
+    /// The OpenPGP public key file to verify the asset with
+    ///
+    /// Either ASCII-armored or binary. The detached signature is downloaded
+    /// from `<asset>.asc` or `<asset>.sig`.
+    #[arg(long)]
+    pgp_key: Option<String>,
+    /// The signer identity to verify the Sigstore bundle of the asset with
+    ///
+    /// A regular expression that is matched against the identity in the
+    /// signing certificate, such as
+    /// `^https://github.com/owner/repo/.github/workflows/release.yml@refs/tags/`.
+    /// The bundle is downloaded from `<asset>.sigstore.json`, `<asset>.sigstore`,
+    /// or `<asset>.bundle` and verified offline. Requires `--cosign-issuer`.
+    #[arg(long, requires = "cosign_issuer")]
+    cosign_identity: Option<String>,
+    /// The OIDC issuer of the signer identity
+    ///
+    /// For example, `https://token.actions.githubusercontent.com` for GitHub Actions.
+    #[arg(long, requires = "cosign_identity")]
+    cosign_issuer: Option<String>,
+    /// The path or URL of the Sigstore bundle
+    ///
+    /// [default: next to the asset]
+    #[arg(long, requires = "cosign_identity")]
+    cosign_bundle: Option<String>,
+    /// The GitHub Actions workflow that must have built the asset
+    ///
+    /// For example, `crate-ci/typos/.github/workflows/release.yml`. Verifies
+    /// the SLSA build provenance attestation for the SHA-256 hash of the asset,
+    /// which is fetched from the GitHub attestations API of the repository.
+    #[arg(long)]
+    require_provenance: Option<String>,
+    /// The path or URL of the provenance attestation bundle
+    ///
+    /// [default: fetched from the GitHub attestations API]
+    #[arg(long, requires = "require_provenance")]
+    provenance_bundle: Option<String>,
+    /// The path to a Sigstore `trusted_root.json`
+    ///
+    /// [default: the embedded Sigstore public-good trust root]
+    #[arg(long)]
+    trusted_root: Option<String>,
+    /// The maximum size of the asset to download, such as `500M` or `2G`
+    ///
+    /// [default: no limit]
+    #[arg(long)]
+    max_size: Option<String>,
+    /// The directory in which downloaded assets are cached by SHA-256 hash
+    ///
+    /// When `--sha` is a SHA-256 hash that is in the cache, the asset is
+    /// installed from the cache without downloading it.
+    ///
+    /// [default: ~/.jas/cache]
+    #[arg(long, env = "JAS_CACHE_DIR")]
+    #[serde(skip)]
+    cache_dir: Option<String>,
+    /// Forbid any network access
+    ///
+    /// A `--gh` repository is resolved via `--lockfile` or the receipt of an
+    /// earlier install, and the asset is read from the cache or `--from-file`.
+    #[arg(long)]
+    #[serde(skip)]
+    offline: bool,
+    /// Read the asset from this file instead of downloading it
+    ///
+    /// The file is verified like a downloaded asset, so `--sha` and the
+    /// signature options still apply.
+    #[arg(long)]
+    #[serde(skip)]
+    from_file: Option<String>,
     /// The directory to install the binary to
     #[arg(long, default_value = DEFAULT_INSTALL_DIR)]
+    #[serde(default = "default_install_dir")]
     dir: String,
     /// The name of the GitHub release asset to install
     #[arg(long)]
     asset_name: Option<String>,
+    /// The format of the asset
+    ///
+    /// [default: detected from the contents of the asset, with the file
+    /// extension as fallback]
+    #[arg(long, value_enum)]
+    format: Option<archive::Format>,
     /// The name of the binary/binaries in the archive
     ///
     /// [default: use simple heuristic to guess]
@@ -96,6 +241,146 @@ pub(crate) struct InstallArgs {
     /// [default: the repo name or guessed from the url]
     #[arg(long)]
     executable_filename: Option<Vec<String>>,
+    /// Unpack the whole archive next to the executables
+    ///
+    /// By default, only the executables are extracted from the archive.
+    #[arg(long)]
+    keep_archive_contents: bool,
+    /// The lockfile to read the asset and SHA-256 hash from
+    ///
+    /// If the lockfile exists and contains the `--gh` repository, the asset
+    /// for the current platform is installed and verified against the locked
+    /// hash. See `jas lock`.
+    ///
+    /// [default: no lockfile, `jas sync` reads `jas.lock`]
+    #[arg(long)]
+    #[serde(skip)]
+    lockfile: Option<String>,
+}
+
+#[derive(Clone, Debug, Parser)]
+pub(crate) struct SyncArgs {
+    /// The manifest listing the tools to install
+    #[arg(long, default_value = "jas.toml")]
+    manifest: String,
+    /// The lockfile to read the assets and SHA-256 hashes from
+    #[arg(long, default_value = DEFAULT_LOCKFILE)]
+    lockfile: String,
+    /// The GitHub token to use
+    ///
+    /// See `jas install --help` for more information.
+    #[arg(long, env = "GITHUB_TOKEN")]
+    gh_token: Option<String>,
+    /// The directory in which downloaded assets are cached by SHA-256 hash
+    ///
+    /// See `jas install --help` for more information.
+    #[arg(long, env = "JAS_CACHE_DIR")]
+    cache_dir: Option<String>,
+    /// Forbid any network access
+    ///
+    /// See `jas install --help` for more information.
+    #[arg(long)]
+    offline: bool,
+}
+
+#[derive(Clone, Debug, Parser)]
+pub(crate) struct LockArgs {
+    /// The manifest listing the tools to lock
+    #[arg(long, default_value = "jas.toml")]
+    manifest: String,
+    /// The lockfile to write
+    #[arg(long, default_value = DEFAULT_LOCKFILE)]
+    lockfile: String,
+    /// The GitHub token to use
+    ///
+    /// See `jas install --help` for more information.
+    #[arg(long, env = "GITHUB_TOKEN")]
+    gh_token: Option<String>,
+}
+
+#[derive(Clone, Debug, Parser)]
+pub(crate) struct ListArgs {
+    /// Print the receipts as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Clone, Debug, Parser)]
+pub(crate) struct UninstallArgs {
+    /// The name of the tool as shown by `jas list`
+    name: String,
+    /// The directory of the install to remove
+    ///
+    /// Only needed if the tool was installed into multiple directories.
+    #[arg(long)]
+    dir: Option<String>,
+}
+
+#[derive(Clone, Debug, Parser)]
+pub(crate) struct VerifyArgs {
+    /// The names of the tools to verify
+    ///
+    /// [default: all installed tools]
+    names: Vec<String>,
+}
+
+#[derive(Clone, Debug, Parser)]
+pub(crate) struct OutdatedArgs {
+    /// The manifest listing more tools to check
+    #[arg(long, default_value = "jas.toml")]
+    manifest: String,
+    /// The GitHub token to use
+    ///
+    /// See `jas install --help` for more information.
+    #[arg(long, env = "GITHUB_TOKEN")]
+    gh_token: Option<String>,
+}
+
+#[derive(Clone, Debug, Parser)]
+pub(crate) struct UpdateArgs {
+    /// The name of the tool as shown by `jas list`
+    name: String,
+    /// The directory of the install to update
+    ///
+    /// Only needed if the tool was installed into multiple directories.
+    #[arg(long)]
+    dir: Option<String>,
+    /// The SHA-256 hash of the new asset
+    #[arg(long)]
+    sha: Option<String>,
+    /// Install the new asset without specifying its SHA-256 hash
+    #[arg(long)]
+    accept_new_sha: bool,
+    /// The GitHub token to use
+    ///
+    /// See `jas install --help` for more information.
+    #[arg(long, env = "GITHUB_TOKEN")]
+    gh_token: Option<String>,
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+pub(crate) enum CacheCommand {
+    /// List the cached assets.
+    List,
+    /// Remove the cached assets that are not used by any installed tool.
+    Prune {
+        /// Also keep the assets that were used in the last number of days
+        #[arg(long, default_value = "0")]
+        keep_days: u64,
+    },
+    /// Remove all cached assets.
+    Clear,
+}
+
+#[derive(Clone, Debug, Parser)]
+pub(crate) struct CacheArgs {
+    #[command(subcommand)]
+    command: CacheCommand,
+    /// The directory in which downloaded assets are cached by SHA-256 hash
+    ///
+    /// [default: ~/.jas/cache]
+    #[arg(long, env = "JAS_CACHE_DIR", global = true)]
+    cache_dir: Option<String>,
 }
 
 #[derive(Clone, Debug, Parser)]
@@ -105,12 +390,30 @@ pub(crate) struct ShowArgs {
     install_dir: bool,
 }
 
+// The task is parsed once, so the size of the variants doesn't matter.
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, clap::Subcommand)]
 pub(crate) enum Task {
     /// Compute the SHA-256 hash of a file or a GitHub repository.
     Sha(ShaArgs),
     /// Install a binary from a GitHub repository.
     Install(InstallArgs),
+    /// Install all tools listed in a `jas.toml` manifest.
+    Sync(SyncArgs),
+    /// Write the assets and SHA-256 hashes for all platforms to a lockfile.
+    Lock(LockArgs),
+    /// List the installed tools.
+    List(ListArgs),
+    /// Remove the files that were placed when installing a tool.
+    Uninstall(UninstallArgs),
+    /// Verify that the installed executables were not modified.
+    Verify(VerifyArgs),
+    /// Show the tools for which a newer GitHub release is available.
+    Outdated(OutdatedArgs),
+    /// Update a tool to the latest GitHub release.
+    Update(UpdateArgs),
+    /// Manage the cache of downloaded assets.
+    Cache(CacheArgs),
     /// Show information.
     Show(ShowArgs),
     /// Print the project's license.
@@ -162,6 +465,30 @@ fn main() {
         Task::Install(args) => {
             install::run(&args);
         }
+        Task::Sync(args) => {
+            sync::run(&args);
+        }
+        Task::Lock(args) => {
+            lock::run(&args);
+        }
+        Task::List(args) => {
+            list::run(&args);
+        }
+        Task::Uninstall(args) => {
+            uninstall::run(&args);
+        }
+        Task::Verify(args) => {
+            verify::run(&args);
+        }
+        Task::Outdated(args) => {
+            outdated::run(&args);
+        }
+        Task::Update(args) => {
+            update::run(&args);
+        }
+        Task::Cache(args) => {
+            cache::run(&args);
+        }
         Task::Show(args) => {
             if args.install_dir {
                 let path = install::interpret_path(DEFAULT_INSTALL_DIR);
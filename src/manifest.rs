@@ -0,0 +1,91 @@
+use crate::abort;
+use crate::InstallArgs;
+use serde::Deserialize;
+use std::path::Path;
+
+/// The contents of a `jas.toml` manifest.
+///
+/// Each `[[tool]]` entry accepts the same keys as the flags of `jas install`,
+/// for example:
+///
+/// ```toml
+/// [[tool]]
+/// gh = "crate-ci/typos@v1.31.1"
+/// sha = "f683c2abeaff70379df7176110100e18150ecd17a4b9785c32908aca11929993"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Manifest {
+    #[serde(default, rename = "tool")]
+    pub(crate) tools: Vec<InstallArgs>,
+}
+
+/// A short description of the tool for in log messages.
+pub(crate) fn tool_label(args: &InstallArgs) -> String {
+    if let Some(gh) = &args.gh {
+        gh.clone()
+    } else if let Some(url) = &args.url {
+        url.clone()
+    } else if let Some(path) = &args.path {
+        path.clone()
+    } else {
+        "<unknown>".to_string()
+    }
+}
+
+fn parse_manifest(text: &str, path: &Path) -> Manifest {
+    match toml::from_str::<Manifest>(text) {
+        Ok(manifest) => manifest,
+        Err(e) => abort(&format!(
+            "Error parsing manifest at {}: {e}",
+            path.display()
+        )),
+    }
+}
+
+pub(crate) fn read_manifest(path: &Path) -> Manifest {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => abort(&format!(
+            "Could not read manifest at {}: {e}",
+            path.display()
+        )),
+    };
+    parse_manifest(&text, path)
+}
+
+#[test]
+fn test_parse_manifest() {
+    let text = r#"
+        [[tool]]
+        gh = "crate-ci/typos@v1.31.1"
+        sha = "f683c2abeaff70379df7176110100e18150ecd17a4b9785c32908aca11929993"
+
+        [[tool]]
+        url = "https://www.johnvansickle.com/ffmpeg/old-releases/ffmpeg-6.0.1-armel-static.tar.xz"
+        dir = "tests"
+        archive-filename = ["ffmpeg", "ffprobe"]
+    "#;
+    let manifest = parse_manifest(text, Path::new("jas.toml"));
+    assert_eq!(manifest.tools.len(), 2);
+    let typos = &manifest.tools[0];
+    assert_eq!(typos.gh.as_deref(), Some("crate-ci/typos@v1.31.1"));
+    assert_eq!(typos.dir, crate::DEFAULT_INSTALL_DIR);
+    assert_eq!(typos.archive_filename, None);
+    let ffmpeg = &manifest.tools[1];
+    assert_eq!(ffmpeg.dir, "tests");
+    assert_eq!(
+        ffmpeg.archive_filename,
+        Some(vec!["ffmpeg".to_string(), "ffprobe".to_string()])
+    );
+}
+
+#[test]
+fn test_tool_label() {
+    let text = r#"
+        [[tool]]
+        path = "vendor/tool.tar.gz"
+    "#;
+    let manifest = parse_manifest(text, Path::new("jas.toml"));
+    assert_eq!(tool_label(&manifest.tools[0]), "vendor/tool.tar.gz");
+}
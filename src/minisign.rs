@@ -0,0 +1,82 @@
+use crate::abort;
+use crate::download::Download;
+use minisign_verify::PublicKey;
+use minisign_verify::Signature;
+use std::io::Read;
+use std::path::Path;
+
+/// Parse the public key from a base64 string such as `RWQf6LRC...` or from a
+/// `minisign.pub` file.
+fn parse_public_key(key: &str) -> PublicKey {
+    let result = if Path::new(key).is_file() {
+        PublicKey::from_file(key)
+    } else {
+        PublicKey::from_base64(key.trim())
+    };
+    match result {
+        Ok(public_key) => public_key,
+        Err(e) => abort(&format!("Invalid minisign public key {key}: {e}")),
+    }
+}
+
+fn verify(mut body: impl Read, signature: &str, public_key: &PublicKey) -> Result<(), String> {
+    let signature = Signature::decode(signature).map_err(|e| format!("invalid signature: {e}"))?;
+    match public_key.verify_stream(&signature) {
+        Ok(mut verifier) => {
+            let mut buffer = vec![0; 64 * 1024];
+            loop {
+                match body.read(&mut buffer).map_err(|e| e.to_string())? {
+                    0 => break,
+                    n => verifier.update(&buffer[..n]),
+                }
+            }
+            verifier.finalize().map_err(|e| e.to_string())?;
+        }
+        // Allow legacy signatures since they are still created by some tools.
+        // These sign the data itself instead of its hash, so cannot be streamed.
+        Err(minisign_verify::Error::UnsupportedLegacyMode) => {
+            let mut data = vec![];
+            body.read_to_end(&mut data).map_err(|e| e.to_string())?;
+            public_key
+                .verify(&data, &signature, true)
+                .map_err(|e| e.to_string())?;
+        }
+        Err(e) => return Err(e.to_string()),
+    }
+    tracing::debug!("Trusted comment: {}", signature.trusted_comment());
+    Ok(())
+}
+
+/// Verify the downloaded asset against the `<url>.minisig` signature.
+pub(crate) fn verify_minisign(download: &Download, url: &str, local: bool, key: &str) {
+    let public_key = parse_public_key(key);
+    let signature_url = format!("{url}.minisig");
+    let signature = crate::download::fetch_file(&signature_url, local);
+    let signature = String::from_utf8_lossy(&signature);
+    match verify(download.open(), &signature, &public_key) {
+        Ok(()) => tracing::info!("Verified minisign signature from {signature_url}"),
+        Err(e) => abort(&format!("Minisign verification failed for {url}: {e}")),
+    }
+}
+
+#[test]
+fn test_verify() {
+    // Test vectors from the minisign-verify crate.
+    let public_key = parse_public_key("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3");
+    let prehashed = "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==";
+    assert_eq!(verify(&b"test"[..], prehashed, &public_key), Ok(()));
+    assert!(verify(&b"Test"[..], prehashed, &public_key).is_err());
+
+    let legacy = "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==";
+    assert_eq!(verify(&b"test"[..], legacy, &public_key), Ok(()));
+    assert!(verify(&b"Test"[..], legacy, &public_key).is_err());
+
+    let other_key = parse_public_key("RWTAPRW2qy9FjsBiMuJ2U9IcvBIzwmb0+1RSK0D2Yz1P9KGwrJQHpSE8");
+    assert!(verify(&b"test"[..], prehashed, &other_key).is_err());
+}
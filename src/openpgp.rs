@@ -0,0 +1,194 @@
+use crate::abort;
+use crate::download::Download;
+use pgp::composed::Deserializable;
+use pgp::composed::DetachedSignature;
+use pgp::composed::SignedPublicKey;
+use pgp::composed::SignedPublicSubKey;
+use pgp::packet::Signature;
+use pgp::packet::SignatureType;
+use pgp::packet::SubpacketData;
+use pgp::types::KeyDetails;
+use pgp::types::Timestamp;
+use std::io::Cursor;
+use std::io::Read;
+
+/// The file extensions that are used for detached signatures next to the asset.
+static SIGNATURE_EXTENSIONS: [&str; 2] = [".asc", ".sig"];
+
+fn is_armored(bytes: &[u8]) -> bool {
+    bytes.trim_ascii_start().starts_with(b"-----BEGIN PGP")
+}
+
+/// Parse an ASCII-armored or binary public key and check its self-signatures.
+fn parse_public_key(bytes: &[u8]) -> Result<SignedPublicKey, String> {
+    let key = if is_armored(bytes) {
+        SignedPublicKey::from_armor_single(Cursor::new(bytes)).map(|(key, _)| key)
+    } else {
+        SignedPublicKey::from_bytes(Cursor::new(bytes))
+    }
+    .map_err(|e| e.to_string())?;
+    key.verify_bindings().map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+fn parse_signature(bytes: &[u8]) -> Result<DetachedSignature, String> {
+    if is_armored(bytes) {
+        DetachedSignature::from_armor_single(Cursor::new(bytes)).map(|(signature, _)| signature)
+    } else {
+        DetachedSignature::from_bytes(Cursor::new(bytes))
+    }
+    .map_err(|e| format!("invalid signature: {e}"))
+}
+
+/// Return the most recent signature, whose subpackets apply to the key.
+fn latest<'a>(signatures: impl Iterator<Item = &'a Signature>) -> Option<&'a Signature> {
+    signatures.max_by_key(|signature| signature.created().map(Timestamp::as_secs))
+}
+
+fn has_key_flags(signature: &Signature) -> bool {
+    signature.config().is_some_and(|config| {
+        config
+            .hashed_subpackets()
+            .any(|subpacket| matches!(subpacket.data, SubpacketData::KeyFlags(_)))
+    })
+}
+
+fn is_expired(created_at: Timestamp, binding: &Signature) -> bool {
+    match binding.key_expiration_time() {
+        // An expiration time of zero means that the key does not expire.
+        Some(duration) if duration.as_secs() != 0 => {
+            let expires_at = u64::from(created_at.as_secs()) + u64::from(duration.as_secs());
+            expires_at <= u64::from(Timestamp::now().as_secs())
+        }
+        _ => false,
+    }
+}
+
+/// Check that the primary key is unrevoked, unexpired, and, if it has key
+/// flags, allowed to sign.
+fn check_primary(key: &SignedPublicKey) -> Result<(), String> {
+    if !key.details.revocation_signatures.is_empty() {
+        return Err("the primary key is revoked".to_string());
+    }
+    let bindings = key.details.direct_signatures.iter().chain(
+        key.details
+            .users
+            .iter()
+            .flat_map(|user| user.signatures.iter()),
+    );
+    if let Some(binding) = latest(bindings) {
+        if has_key_flags(binding) && !binding.key_flags().sign() {
+            return Err("the primary key is not allowed to sign".to_string());
+        }
+        if is_expired(key.primary_key.created_at(), binding) {
+            return Err("the primary key is expired".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Check that the subkey is unrevoked, unexpired, and allowed to sign.
+fn check_subkey(subkey: &SignedPublicSubKey) -> Result<(), String> {
+    let signatures = |typ| {
+        subkey
+            .signatures
+            .iter()
+            .filter(move |signature| signature.typ() == Some(typ))
+    };
+    if signatures(SignatureType::SubkeyRevocation).next().is_some() {
+        return Err("the subkey is revoked".to_string());
+    }
+    let bindings = signatures(SignatureType::SubkeyBinding);
+    let binding = match latest(bindings) {
+        Some(binding) => binding,
+        None => return Err("the subkey has no binding signature".to_string()),
+    };
+    if !binding.key_flags().sign() {
+        return Err("the subkey is not allowed to sign".to_string());
+    }
+    if is_expired(subkey.key.created_at(), binding) {
+        return Err("the subkey is expired".to_string());
+    }
+    Ok(())
+}
+
+/// Verify the signature with the primary key or one of the subkeys.
+///
+/// Only keys that are allowed to sign and are neither revoked nor expired are
+/// accepted. The data is read again for each key that is tried.
+fn verify<R: Read>(
+    open: impl Fn() -> R,
+    signature: &[u8],
+    key: &SignedPublicKey,
+) -> Result<(), String> {
+    let signature = parse_signature(signature)?.signature;
+    let primary = signature.verify(key, open());
+    if primary.is_ok() {
+        return check_primary(key);
+    }
+    for subkey in &key.public_subkeys {
+        if signature.verify(subkey, open()).is_ok() {
+            return check_subkey(subkey);
+        }
+    }
+    Err(primary.unwrap_err().to_string())
+}
+
+/// Verify the downloaded asset against the `<url>.asc` or `<url>.sig` signature.
+pub(crate) fn verify_pgp(download: &Download, url: &str, local: bool, key_path: &str) {
+    let key = std::fs::read(key_path)
+        .unwrap_or_else(|e| abort(&format!("Could not read PGP key at {key_path}: {e}")));
+    let key = parse_public_key(&key)
+        .unwrap_or_else(|e| abort(&format!("Invalid PGP public key {key_path}: {e}")));
+    for extension in SIGNATURE_EXTENSIONS {
+        let signature_url = format!("{url}{extension}");
+        let signature = match crate::download::fetch_file_core(&signature_url, local) {
+            Ok(signature) => signature,
+            Err(e) => {
+                tracing::debug!("No signature at {signature_url}: {e}");
+                continue;
+            }
+        };
+        match verify(|| download.open(), &signature, &key) {
+            Ok(()) => {
+                tracing::info!("Verified PGP signature from {signature_url}");
+                return;
+            }
+            Err(e) => abort(&format!("PGP verification failed for {url}: {e}")),
+        }
+    }
+    abort(&format!(
+        "Could not find a PGP signature at {url}.asc or {url}.sig"
+    ));
+}
+
+#[test]
+fn test_verify() {
+    let body = || &include_bytes!("../tests/data/pgp/hello.txt")[..];
+    let rsa = parse_public_key(include_bytes!("../tests/data/pgp/rsa.pub.asc")).unwrap();
+    let ed25519 = parse_public_key(include_bytes!("../tests/data/pgp/ed25519.pub.asc")).unwrap();
+    // Armored signature by the primary key.
+    let armored = include_bytes!("../tests/data/pgp/hello.txt.rsa.asc");
+    assert_eq!(verify(body, armored, &rsa), Ok(()));
+    // Binary signature by a signing subkey.
+    let binary = include_bytes!("../tests/data/pgp/hello.txt.ed25519.sig");
+    assert_eq!(verify(body, binary, &ed25519), Ok(()));
+
+    assert!(verify(|| &b"hello!\n"[..], armored, &rsa).is_err());
+    assert!(verify(body, armored, &ed25519).is_err());
+    assert!(verify(body, binary, &rsa).is_err());
+    assert!(verify(body, b"not a signature", &rsa).is_err());
+
+    let revoked = parse_public_key(include_bytes!("../tests/data/pgp/revoked.pub.asc")).unwrap();
+    let signature = include_bytes!("../tests/data/pgp/hello.txt.revoked.sig");
+    assert_eq!(
+        verify(body, signature, &revoked),
+        Err("the subkey is revoked".to_string())
+    );
+    let expired = parse_public_key(include_bytes!("../tests/data/pgp/expired.pub.asc")).unwrap();
+    let signature = include_bytes!("../tests/data/pgp/hello.txt.expired.sig");
+    assert_eq!(
+        verify(body, signature, &expired),
+        Err("the subkey is expired".to_string())
+    );
+}
@@ -0,0 +1,108 @@
+use crate::manifest::read_manifest;
+use crate::receipt::read_receipts;
+use crate::OutdatedArgs;
+use std::path::Path;
+
+/// A tool that was installed or is listed in the manifest.
+#[derive(Debug)]
+struct Pinned {
+    name: String,
+    owner: String,
+    repo: String,
+    tag: String,
+}
+
+fn push_unique(pinned: &mut Vec<Pinned>, tool: Pinned) {
+    let exists = pinned
+        .iter()
+        .any(|p| p.owner == tool.owner && p.repo == tool.repo && p.tag == tool.tag);
+    if !exists {
+        pinned.push(tool);
+    }
+}
+
+/// Whether the tag pins a release, unlike `latest` or a requirement such as
+/// `^1.31`, which already follow new releases.
+fn is_exact(tag: &str) -> bool {
+    tag != "latest" && !crate::tag::is_version_req(tag)
+}
+
+fn pinned_tools(args: &OutdatedArgs) -> Vec<Pinned> {
+    let mut pinned = vec![];
+    for receipt in read_receipts() {
+        if let (Some(gh), Some(tag)) = (&receipt.gh, &receipt.tag) {
+            if !is_exact(tag) {
+                continue;
+            }
+            let (owner, repo, _) = crate::gh::parse_gh(gh);
+            push_unique(
+                &mut pinned,
+                Pinned {
+                    name: receipt.name.clone(),
+                    owner: owner.to_string(),
+                    repo: repo.to_string(),
+                    tag: tag.clone(),
+                },
+            );
+        }
+    }
+    let manifest = Path::new(&args.manifest);
+    if manifest.exists() {
+        for tool in read_manifest(manifest).tools {
+            if let Some(gh) = &tool.gh {
+                if let (owner, repo, Some(tag)) = crate::gh::parse_gh(gh) {
+                    if !is_exact(tag) {
+                        tracing::debug!("Skipping {gh} since it follows new releases");
+                        continue;
+                    }
+                    push_unique(
+                        &mut pinned,
+                        Pinned {
+                            name: repo.to_string(),
+                            owner: owner.to_string(),
+                            repo: repo.to_string(),
+                            tag: tag.to_string(),
+                        },
+                    );
+                }
+            }
+        }
+    }
+    pinned
+}
+
+/// Print the current and latest tag for each tool from a GitHub release.
+pub(crate) fn run(args: &OutdatedArgs) {
+    let pinned = pinned_tools(args);
+    if pinned.is_empty() {
+        tracing::info!("No tools from GitHub releases installed or listed in the manifest");
+        return;
+    }
+    let header = ["NAME", "SOURCE", "CURRENT", "LATEST"];
+    let mut rows = vec![header.iter().map(|s| s.to_string()).collect::<Vec<_>>()];
+    let mut outdated = 0;
+    for tool in &pinned {
+        let release = crate::gh::get_latest_release(&tool.owner, &tool.repo, &args.gh_token);
+        let latest = crate::gh::release_tag(&release);
+        if latest != tool.tag {
+            outdated += 1;
+        }
+        rows.push(vec![
+            tool.name.clone(),
+            format!("{}/{}", tool.owner, tool.repo),
+            tool.tag.clone(),
+            latest.to_string(),
+        ]);
+    }
+    print!("{}", crate::list::format_rows(&rows));
+    tracing::info!("{outdated} of {} tools are outdated", pinned.len());
+}
+
+#[test]
+fn test_is_exact() {
+    assert!(is_exact("v1.31.1"));
+    assert!(is_exact("1.40.0"));
+    assert!(!is_exact("latest"));
+    assert!(!is_exact("^1.31"));
+    assert!(!is_exact("~0.13"));
+}
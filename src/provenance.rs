@@ -0,0 +1,124 @@
+use crate::abort;
+use crate::download::Download;
+use crate::InstallArgs;
+use serde_json::Value;
+
+/// The prefix of the predicate types for SLSA build provenance.
+static SLSA_PROVENANCE: &str = "https://slsa.dev/provenance/";
+
+/// The OIDC issuer for GitHub Actions.
+static GITHUB_ISSUER: &str = "https://token.actions.githubusercontent.com";
+
+/// Split `owner/repo/.github/workflows/release.yml` into the owner and repo.
+fn parse_workflow(workflow: &str) -> (&str, &str) {
+    let mut parts = workflow.splitn(3, '/');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(owner), Some(repo), Some(path)) if path.starts_with(".github/workflows/") => {
+            (owner, repo)
+        }
+        _ => abort(&format!(
+            "Expected `--require-provenance` in the form owner/repo/.github/workflows/release.yml, got {workflow}"
+        )),
+    }
+}
+
+/// The certificate identity of a run of the workflow for any ref, such as
+/// `https://github.com/owner/repo/.github/workflows/release.yml@refs/tags/v1.0.0`.
+fn workflow_identity(workflow: &str) -> String {
+    format!("^https://github\\.com/{}@", regex::escape(workflow))
+}
+
+/// Verify that the in-toto statement is build provenance for the asset.
+fn verify_statement(statement: &Value, sha: &str) -> Result<(), String> {
+    let predicate_type = statement["predicateType"].as_str().unwrap_or_default();
+    if !predicate_type.starts_with(SLSA_PROVENANCE) {
+        return Err(format!(
+            "the attestation is not SLSA build provenance but {predicate_type}"
+        ));
+    }
+    let subjects = statement["subject"].as_array().map(Vec::as_slice);
+    let covered = subjects
+        .unwrap_or(&[])
+        .iter()
+        .any(|subject| subject["digest"]["sha256"] == sha);
+    if !covered {
+        return Err(format!(
+            "the attestation is not for the asset with SHA-256 {sha}"
+        ));
+    }
+    Ok(())
+}
+
+/// Verify that the downloaded asset was built by `workflow`.
+///
+/// The attestation bundles are taken from `--provenance-bundle` or fetched
+/// from the GitHub attestations API of the repository of the workflow, and
+/// are verified offline against the trust root.
+pub(crate) fn verify_provenance(download: &Download, args: &InstallArgs, workflow: &str) {
+    let (owner, repo) = parse_workflow(workflow);
+    let sha = download.sha256();
+    let bundles = match &args.provenance_bundle {
+        Some(location) => vec![crate::sigstore::read_bundle(location)],
+        None => crate::gh::get_attestations(owner, repo, &sha, &args.gh_token)
+            .iter()
+            .map(|bundle| bundle.to_string().into_bytes())
+            .collect(),
+    };
+    if bundles.is_empty() {
+        abort(&format!(
+            "Could not find attestations for the asset with SHA-256 {sha} in {owner}/{repo}"
+        ));
+    }
+    let root = crate::sigstore::load_trusted_root(&args.trusted_root);
+    let identity = workflow_identity(workflow);
+    let mut errors = vec![];
+    for bundle in &bundles {
+        let result = crate::sigstore::parse_bundle(bundle)
+            .and_then(|bundle| {
+                crate::sigstore::verify_attestation(&bundle, &root, &identity, GITHUB_ISSUER)
+            })
+            .and_then(|statement| verify_statement(&statement, &sha));
+        match result {
+            Ok(()) => {
+                tracing::info!("Verified build provenance from {workflow}");
+                return;
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    abort(&format!(
+        "Provenance verification failed for the asset with SHA-256 {sha}:\n{}",
+        errors.join("\n")
+    ));
+}
+
+#[test]
+fn test_workflow_identity() {
+    let workflow = "crate-ci/typos/.github/workflows/release.yml";
+    assert_eq!(parse_workflow(workflow), ("crate-ci", "typos"));
+    let regex = regex::Regex::new(&workflow_identity(workflow)).unwrap();
+    let identity =
+        "https://github.com/crate-ci/typos/.github/workflows/release.yml@refs/tags/v1.31.1";
+    assert!(regex.is_match(identity));
+    let other =
+        "https://github.com/crate-ci/typos/.github/workflows/release.yml.evil@refs/heads/main";
+    assert!(!regex.is_match(other));
+    let other = "https://github.com/crate-ci/typos/.github/workflows/releaseXyml@refs/heads/main";
+    assert!(!regex.is_match(other));
+}
+
+#[test]
+fn test_verify_statement() {
+    let sha = "c811d58de79c92f03214e63aa339484e488d694ae8a6283b5f3f17a9faf50172";
+    let statement = serde_json::json!({
+        "_type": "https://in-toto.io/Statement/v1",
+        "subject": [{ "name": "typos.tar.gz", "digest": { "sha256": sha } }],
+        "predicateType": "https://slsa.dev/provenance/v1",
+    });
+    assert_eq!(verify_statement(&statement, sha), Ok(()));
+    let other = "a172195e1b1f1e011b3034913d1c87f0bbf0552a096b4ead0e3fa0620f4329cd";
+    assert!(verify_statement(&statement, other).is_err());
+    let mut signature = statement.clone();
+    signature["predicateType"] = "https://sigstore.dev/cosign/sign/v1".into();
+    assert!(verify_statement(&signature, sha).is_err());
+}
@@ -0,0 +1,311 @@
+use crate::abort;
+use crate::archive::Format;
+use crate::install::interpret_path;
+use serde::Deserialize;
+use serde::Serialize;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// The file in which the receipts of all installed tools are stored.
+static RECEIPTS_FILE: &str = "~/.jas/receipts.json";
+
+/// A file that was placed by `jas install`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct InstalledFile {
+    pub(crate) path: PathBuf,
+    /// The SHA-256 hash of the file right after it was placed.
+    pub(crate) sha: String,
+}
+
+/// A record of where an installed tool came from and what was placed.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Receipt {
+    pub(crate) name: String,
+    /// The GitHub repository in the form `owner/repo`.
+    pub(crate) gh: Option<String>,
+    pub(crate) tag: Option<String>,
+    /// The URL that the asset was downloaded from.
+    pub(crate) url: String,
+    pub(crate) asset_name: String,
+    /// The SHA-256 hash of the downloaded asset.
+    pub(crate) sha: String,
+    /// The directory into which the tool was installed.
+    ///
+    /// Receipts written before this was recorded use the directory of the
+    /// first executable.
+    #[serde(default)]
+    pub(crate) dir: PathBuf,
+    /// The `--format` that overrode the detected format, if any.
+    #[serde(default)]
+    pub(crate) format: Option<Format>,
+    #[serde(default)]
+    pub(crate) archive_filename: Option<Vec<String>>,
+    #[serde(default)]
+    pub(crate) executable_filename: Option<Vec<String>>,
+    pub(crate) executables: Vec<InstalledFile>,
+    /// The directory into which the archive was unpacked, if any.
+    pub(crate) archive_dir: Option<PathBuf>,
+    /// The files in `archive_dir` right after unpacking.
+    #[serde(default)]
+    pub(crate) archive_files: Vec<InstalledFile>,
+    pub(crate) installed_at: String,
+    pub(crate) jas_version: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Receipts {
+    receipts: Vec<Receipt>,
+}
+
+fn receipts_path() -> PathBuf {
+    interpret_path(RECEIPTS_FILE)
+}
+
+fn parse_receipts(text: &str) -> Result<Vec<Receipt>, serde_json::Error> {
+    let mut receipts = serde_json::from_str::<Receipts>(text)?.receipts;
+    for receipt in &mut receipts {
+        if receipt.dir.as_os_str().is_empty() {
+            let parent = receipt
+                .executables
+                .first()
+                .and_then(|executable| executable.path.parent());
+            if let Some(parent) = parent {
+                receipt.dir = parent.to_path_buf();
+            }
+        }
+    }
+    Ok(receipts)
+}
+
+/// Read the receipts of all installed tools.
+pub(crate) fn read_receipts() -> Vec<Receipt> {
+    let path = receipts_path();
+    if !path.exists() {
+        return vec![];
+    }
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) => abort(&format!(
+            "Could not read receipts at {}: {e}",
+            path.display()
+        )),
+    };
+    match parse_receipts(&text) {
+        Ok(receipts) => receipts,
+        Err(e) => abort(&format!(
+            "Error parsing receipts at {}: {e}",
+            path.display()
+        )),
+    }
+}
+
+fn write_receipts(receipts: Vec<Receipt>) {
+    let path = receipts_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).unwrap();
+    }
+    let receipts = Receipts { receipts };
+    let text = serde_json::to_string_pretty(&receipts).unwrap();
+    if let Err(e) = std::fs::write(&path, text) {
+        abort(&format!(
+            "Could not write receipts at {}: {e}",
+            path.display()
+        ));
+    }
+    tracing::debug!("Wrote receipts to {}", path.display());
+}
+
+impl Receipt {
+    /// Whether both receipts are for the same install, that is, the same tool
+    /// in the same directory.
+    pub(crate) fn same_install(&self, other: &Receipt) -> bool {
+        self.name == other.name && self.dir == other.dir
+    }
+
+    /// Whether the install placed the executable or unpacked the archive at `path`.
+    pub(crate) fn claims(&self, path: &Path) -> bool {
+        self.archive_dir.as_deref() == Some(path)
+            || self.executables.iter().any(|file| file.path == path)
+    }
+}
+
+/// Store the receipt, replacing any earlier receipt for the same install.
+///
+/// The paths that the install overwrote are dropped from the other receipts,
+/// so that uninstalling those tools leaves the new files alone.
+pub(crate) fn add_receipt(receipt: Receipt) {
+    let mut receipts = read_receipts();
+    receipts.retain(|current| !current.same_install(&receipt));
+    for other in &mut receipts {
+        other.executables.retain(|file| !receipt.claims(&file.path));
+        if other
+            .archive_dir
+            .as_deref()
+            .is_some_and(|dir| receipt.claims(dir))
+        {
+            other.archive_dir = None;
+            other.archive_files.clear();
+        }
+    }
+    receipts.push(receipt);
+    receipts.sort_by(|a, b| (&a.name, &a.dir).cmp(&(&b.name, &b.dir)));
+    write_receipts(receipts);
+}
+
+/// Remove the receipt of the install.
+pub(crate) fn remove_receipt(receipt: &Receipt) {
+    let mut receipts = read_receipts();
+    receipts.retain(|current| !current.same_install(receipt));
+    write_receipts(receipts);
+}
+
+/// Find the receipt of the tool with the given name.
+///
+/// If the tool was installed into multiple directories, `dir` selects one.
+pub(crate) fn find_receipt<'a>(
+    receipts: &'a [Receipt],
+    name: &str,
+    dir: &Option<String>,
+) -> Result<&'a Receipt, String> {
+    let dir = dir
+        .as_deref()
+        .map(|dir| std::path::absolute(interpret_path(dir)).unwrap());
+    let matches = receipts
+        .iter()
+        .filter(|receipt| receipt.name == name)
+        .filter(|receipt| dir.as_ref().is_none_or(|dir| &receipt.dir == dir))
+        .collect::<Vec<_>>();
+    match matches.as_slice() {
+        [receipt] => Ok(receipt),
+        [] => Err(format!(
+            "No receipt found for {name}; see `jas list` for the installed tools"
+        )),
+        _ => Err(format!(
+            "{name} is installed in multiple directories; pass `--dir` to select one of\n{}",
+            matches
+                .iter()
+                .map(|receipt| receipt.dir.display().to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        )),
+    }
+}
+
+/// Find the receipt of an earlier install of the `--gh` repository.
+///
+/// An exact tag must match the recorded tag and a semver requirement such as
+/// `^1.31` must match its version. Without a tag or with `latest`, any
+/// recorded tag matches.
+pub(crate) fn find_gh_receipt<'a>(receipts: &'a [Receipt], gh: &str) -> Option<&'a Receipt> {
+    let (owner, repo, tag) = crate::gh::parse_gh(gh);
+    let repository = format!("{owner}/{repo}");
+    receipts
+        .iter()
+        .filter(|receipt| receipt.gh.as_deref() == Some(repository.as_str()))
+        .find(|receipt| match (tag, receipt.tag.as_deref()) {
+            (None | Some("latest"), _) => true,
+            (Some(req), Some(recorded)) if crate::tag::is_version_req(req) => {
+                let req = semver::VersionReq::parse(req).ok();
+                let version = crate::tag::parse_tag_version(recorded);
+                matches!((req, version), (Some(req), Some(version)) if req.matches(&version))
+            }
+            (Some(tag), recorded) => recorded == Some(tag),
+        })
+}
+
+#[test]
+fn test_find_receipt() {
+    let receipt = |dir: &str| Receipt {
+        name: "typos".to_string(),
+        gh: None,
+        tag: None,
+        url: "https://example.com/typos".to_string(),
+        asset_name: "typos".to_string(),
+        sha: "f683c2abeaff70379df7176110100e18150ecd17a4b9785c32908aca11929993".to_string(),
+        dir: PathBuf::from(dir),
+        format: None,
+        archive_filename: None,
+        executable_filename: None,
+        executables: vec![],
+        archive_dir: None,
+        archive_files: vec![],
+        installed_at: "2025-04-08T15:05:06Z".to_string(),
+        jas_version: "0.3.2".to_string(),
+    };
+    let receipts = [receipt("/home/user/.jas/bin"), receipt("/project/tools")];
+    assert!(!receipts[0].same_install(&receipts[1]));
+    let find = |name, dir: Option<&str>| {
+        find_receipt(&receipts, name, &dir.map(|dir| dir.to_string()))
+            .map(|receipt| receipt.dir.display().to_string())
+    };
+    assert_eq!(
+        find("typos", Some("/project/tools")),
+        Ok("/project/tools".to_string())
+    );
+    assert!(find("typos", None)
+        .unwrap_err()
+        .contains("installed in multiple directories"));
+    assert!(find("just", None).unwrap_err().contains("No receipt found"));
+}
+
+#[test]
+fn test_find_gh_receipt() {
+    let receipt = Receipt {
+        name: "typos".to_string(),
+        gh: Some("crate-ci/typos".to_string()),
+        tag: Some("v1.31.1".to_string()),
+        url: "https://github.com/crate-ci/typos/releases/download/v1.31.1/typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz".to_string(),
+        asset_name: "typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz".to_string(),
+        sha: "f683c2abeaff70379df7176110100e18150ecd17a4b9785c32908aca11929993".to_string(),
+        dir: PathBuf::from("/home/user/.jas/bin"),
+        format: None,
+        archive_filename: None,
+        executable_filename: None,
+        executables: vec![],
+        archive_dir: None,
+        archive_files: vec![],
+        installed_at: "2025-04-08T15:05:06Z".to_string(),
+        jas_version: "0.3.2".to_string(),
+    };
+    let receipts = [receipt];
+    let find = |gh| find_gh_receipt(&receipts, gh).map(|receipt| receipt.name.as_str());
+    assert_eq!(find("crate-ci/typos@v1.31.1"), Some("typos"));
+    assert_eq!(find("crate-ci/typos"), Some("typos"));
+    assert_eq!(find("crate-ci/typos@latest"), Some("typos"));
+    assert_eq!(find("crate-ci/typos@^1.31"), Some("typos"));
+    assert_eq!(find("crate-ci/typos@^1.32"), None);
+    assert_eq!(find("crate-ci/typos@v1.32.0"), None);
+    assert_eq!(find("casey/just"), None);
+}
+
+#[test]
+fn test_parse_receipts_without_dir() {
+    // Receipts as written before the install directory was recorded.
+    let text = r#"{
+  "receipts": [
+    {
+      "name": "typos",
+      "gh": "crate-ci/typos",
+      "tag": "v1.31.1",
+      "url": "https://github.com/crate-ci/typos/releases/download/v1.31.1/typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz",
+      "asset_name": "typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz",
+      "sha": "f683c2abeaff70379df7176110100e18150ecd17a4b9785c32908aca11929993",
+      "executables": [
+        {
+          "path": "/home/user/.jas/bin/typos",
+          "sha": "0fb2401a46409bdf574f42f92df0418934166032ec2bcb0fc7919b7664fdcc01"
+        }
+      ],
+      "archive_dir": null,
+      "installed_at": "2025-04-08T15:05:06Z",
+      "jas_version": "0.3.2"
+    }
+  ]
+}"#;
+    let receipts = parse_receipts(text).unwrap();
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].dir, PathBuf::from("/home/user/.jas/bin"));
+    assert_eq!(receipts[0].format, None);
+    assert_eq!(receipts[0].archive_filename, None);
+    assert_eq!(receipts[0].executable_filename, None);
+}
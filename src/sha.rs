@@ -1,7 +1,10 @@
 use crate::abort;
 use crate::ShaArgs;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use sha2::Digest;
 use sha2::Sha256;
+use sha2::Sha512;
 use std::fmt;
 use std::fmt::Display;
 use std::path::PathBuf;
@@ -54,6 +57,174 @@ impl PartialEq<Sha256Hash> for String {
     }
 }
 
+/// A hash algorithm that can be used to pin an asset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    fn prefix(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "SHA-256",
+            HashAlgorithm::Sha512 => "SHA-512",
+            HashAlgorithm::Blake3 => "BLAKE3",
+        }
+    }
+
+    pub fn hasher(&self) -> Hasher {
+        match self {
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlgorithm::Sha512 => Hasher::Sha512(Sha512::new()),
+            HashAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+/// An incremental hasher for any of the supported algorithms.
+pub enum Hasher {
+    Sha256(Sha256),
+    Sha512(Sha512),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Sha512(hasher) => hasher.update(data),
+            Hasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        match self {
+            Hasher::Sha256(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha512(hasher) => hasher.finalize().to_vec(),
+            Hasher::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Notation {
+    /// Hex without prefix, which is always SHA-256.
+    Hex,
+    /// Hex with the algorithm as prefix, such as `sha512:<hex>`.
+    Prefixed,
+    /// Base64 as used for Subresource Integrity, such as `sha256-<base64>`.
+    Sri,
+}
+
+/// An expected hash as passed via `--sha`.
+///
+/// Supports plain SHA-256 hex digests, digests prefixed with the algorithm
+/// such as `sha512:<hex>` or `blake3:<hex>`, and Subresource Integrity digests
+/// such as `sha256-<base64>`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HashPin {
+    pub algorithm: HashAlgorithm,
+    pub digest: Vec<u8>,
+    notation: Notation,
+}
+
+impl HashPin {
+    pub fn parse(text: &str) -> Result<HashPin, String> {
+        let text = text.trim();
+        let algorithms = [
+            HashAlgorithm::Sha256,
+            HashAlgorithm::Sha512,
+            HashAlgorithm::Blake3,
+        ];
+        let mut pin = None;
+        for algorithm in algorithms {
+            let prefix = algorithm.prefix();
+            if let Some(hex) = text.strip_prefix(&format!("{prefix}:")) {
+                let digest = hex::decode(hex).map_err(|e| format!("invalid hex in {text}: {e}"))?;
+                pin = Some((algorithm, digest, Notation::Prefixed));
+            } else if let Some(base64) = text.strip_prefix(&format!("{prefix}-")) {
+                if algorithm == HashAlgorithm::Blake3 {
+                    return Err("Subresource Integrity does not support BLAKE3".to_string());
+                }
+                let digest = BASE64
+                    .decode(base64)
+                    .map_err(|e| format!("invalid base64 in {text}: {e}"))?;
+                pin = Some((algorithm, digest, Notation::Sri));
+            }
+        }
+        let (algorithm, digest, notation) = match pin {
+            Some(pin) => pin,
+            None => {
+                let digest = hex::decode(text).map_err(|_| {
+                    format!("expected a SHA-256 hex digest or a digest such as sha512:<hex>, got {text}")
+                })?;
+                (HashAlgorithm::Sha256, digest, Notation::Hex)
+            }
+        };
+        let expected = algorithm.digest(b"").len();
+        if digest.len() != expected {
+            return Err(format!(
+                "expected a {} digest of {expected} bytes, got {} bytes in {text}",
+                algorithm.label(),
+                digest.len()
+            ));
+        }
+        Ok(HashPin {
+            algorithm,
+            digest,
+            notation,
+        })
+    }
+
+    /// Format a digest in the same notation as this pin.
+    fn format(&self, digest: &[u8]) -> String {
+        let prefix = self.algorithm.prefix();
+        match self.notation {
+            Notation::Hex => hex::encode(digest),
+            Notation::Prefixed => format!("{prefix}:{}", hex::encode(digest)),
+            Notation::Sri => format!("{prefix}-{}", BASE64.encode(digest)),
+        }
+    }
+}
+
+/// Parse the expected hash from `--sha`.
+pub fn parse_pin(expected: &str) -> HashPin {
+    match HashPin::parse(expected) {
+        Ok(pin) => pin,
+        Err(e) => abort(&format!("Invalid `--sha`: {e}")),
+    }
+}
+
+/// Abort if the digest does not match the expected hash.
+pub fn verify_pin(pin: &HashPin, actual: &[u8]) {
+    if actual != pin.digest {
+        abort(&format!(
+            "{} mismatch: expected\n{}, but got\n{}",
+            pin.algorithm.label(),
+            pin.format(&pin.digest),
+            pin.format(actual)
+        ));
+    }
+}
+
 #[test]
 fn test_hash() {
     let text = b"hello world";
@@ -61,12 +232,52 @@ fn test_hash() {
     assert_eq!(Sha256Hash::from_data(text), *expected);
 }
 
-fn hash_from_path(path: &str) -> Sha256Hash {
+#[test]
+fn test_hash_pin() {
+    let data = b"hello world";
+    let sha256 = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+    // Obtained via `sha512sum` and `b3sum`.
+    let sha512 = "309ecc489c12d6eb4cc40f50c902f2b4d0ed77ee511a7c7a9bcd3ca86d4cd86f989dd35bc5ff499670da34255b45b0cfd830e81f605dcf7dc5542e93ae9cd76f";
+    let blake3 = "d74981efa70a0c880b8d8c1985d075dbcbf679b99a5f9914e5aaf96b831a9e24";
+    let pins = [
+        sha256.to_string(),
+        format!("sha256:{sha256}"),
+        format!("sha512:{sha512}"),
+        format!("blake3:{blake3}"),
+        "sha256-uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=".to_string(),
+    ];
+    for pin in &pins {
+        let pin = HashPin::parse(pin).unwrap();
+        assert_eq!(pin.algorithm.digest(data), pin.digest);
+    }
+    let pin = HashPin::parse(&pins[4]).unwrap();
+    assert_eq!(pin.format(&pin.digest), pins[4]);
+    assert_eq!(
+        HashPin::parse(&pins[2]).unwrap().algorithm,
+        HashAlgorithm::Sha512
+    );
+
+    assert!(HashPin::parse("sha512:abc").is_err());
+    assert!(HashPin::parse(sha512).is_err());
+    assert!(HashPin::parse(&format!("md5:{sha256}")).is_err());
+    assert!(HashPin::parse(&format!("blake3-{sha256}")).is_err());
+}
+
+fn digest_from_path(algorithm: HashAlgorithm, path: &str) -> Vec<u8> {
     let path = PathBuf::from(path);
     if !path.exists() {
         abort(&format!("Path does not exist: {}", path.display()));
     }
-    Sha256Hash::from_path(&path)
+    let mut file = std::io::BufReader::new(std::fs::File::open(&path).unwrap());
+    let mut hasher = algorithm.hasher();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        match std::io::Read::read(&mut file, &mut buffer).unwrap() {
+            0 => break,
+            n => hasher.update(&buffer[..n]),
+        }
+    }
+    hasher.finalize()
 }
 
 fn prefix_proto_if_needed(url: &str) -> String {
@@ -77,20 +288,30 @@ fn prefix_proto_if_needed(url: &str) -> String {
     }
 }
 
-fn hash_from_url(url: &str) -> Sha256Hash {
+fn digest_from_url(algorithm: HashAlgorithm, url: &str) -> Vec<u8> {
     let url = prefix_proto_if_needed(url);
-    let body = crate::install::download_file(&url);
-    Sha256Hash::from_data(&body)
+    let download = crate::download::download_to_file(&url, &[algorithm], None);
+    download.digest(algorithm).to_vec()
+}
+
+/// Format the digest such that it can be passed to `--sha`.
+///
+/// SHA-256 is printed without prefix for backwards compatibility.
+fn format_digest(algorithm: HashAlgorithm, digest: &[u8]) -> String {
+    let digest = hex::encode(digest);
+    match algorithm {
+        HashAlgorithm::Sha256 => digest,
+        _ => format!("{}:{digest}", algorithm.prefix()),
+    }
 }
 
 pub fn run(args: &ShaArgs) {
-    if let Some(path) = &args.path {
-        let digest = hash_from_path(path);
-        println!("{}", digest);
+    let digest = if let Some(path) = &args.path {
+        digest_from_path(args.algo, path)
     } else if let Some(url) = &args.url {
-        let digest = hash_from_url(url);
-        println!("{}", digest);
+        digest_from_url(args.algo, url)
     } else {
         abort("Specify either a path or a URL");
-    }
+    };
+    println!("{}", format_digest(args.algo, &digest));
 }
@@ -0,0 +1,981 @@
+use crate::abort;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use regex::Regex;
+use serde_json::Value;
+use sha2::Digest;
+use x509_cert::der::asn1::Utf8StringRef;
+use x509_cert::der::oid::db::rfc5280::ID_KP_CODE_SIGNING;
+use x509_cert::der::oid::db::rfc5912::ECDSA_WITH_SHA_256;
+use x509_cert::der::oid::db::rfc5912::ECDSA_WITH_SHA_384;
+use x509_cert::der::oid::db::rfc5912::SECP_256_R_1;
+use x509_cert::der::oid::db::rfc5912::SECP_384_R_1;
+use x509_cert::der::oid::ObjectIdentifier;
+use x509_cert::der::Decode;
+use x509_cert::der::DecodePem;
+use x509_cert::der::Encode;
+use x509_cert::ext::pkix::name::GeneralName;
+use x509_cert::ext::pkix::ExtendedKeyUsage;
+use x509_cert::ext::pkix::SubjectAltName;
+use x509_cert::spki::SubjectPublicKeyInfoOwned;
+use x509_cert::Certificate;
+
+/// The Sigstore public-good trust root.
+///
+/// Taken from the `trusted_root.json` that is distributed via TUF by the
+/// Sigstore project. Can be replaced with `--trusted-root` when the keys rotate.
+static EMBEDDED_TRUSTED_ROOT: &str = include_str!("trusted_root.json");
+
+/// The OIDC issuer extension set by Fulcio.
+pub(crate) const OID_ISSUER_V2: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.57264.1.8");
+
+/// The deprecated OIDC issuer extension, which is still set by Fulcio.
+const OID_ISSUER_V1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.57264.1.1");
+
+#[derive(Clone, Copy)]
+enum HashAlgorithm {
+    Sha256,
+    Sha384,
+}
+
+fn digest(hash: HashAlgorithm, message: &[u8]) -> Vec<u8> {
+    match hash {
+        HashAlgorithm::Sha256 => sha2::Sha256::digest(message).to_vec(),
+        HashAlgorithm::Sha384 => sha2::Sha384::digest(message).to_vec(),
+    }
+}
+
+fn curve(key: &SubjectPublicKeyInfoOwned) -> Result<ObjectIdentifier, String> {
+    key.algorithm
+        .parameters
+        .as_ref()
+        .and_then(|parameters| parameters.decode_as::<ObjectIdentifier>().ok())
+        .ok_or("unsupported public key".to_string())
+}
+
+/// Verify a DER-encoded ECDSA signature over a hash.
+fn verify_ecdsa_prehash(
+    key: &SubjectPublicKeyInfoOwned,
+    prehash: &[u8],
+    signature: &[u8],
+) -> Result<(), String> {
+    use p256::ecdsa::signature::hazmat::PrehashVerifier;
+    let curve = curve(key)?;
+    let point = key.subject_public_key.raw_bytes();
+    let invalid = |e: p256::ecdsa::Error| format!("invalid signature: {e}");
+    if curve == SECP_256_R_1 {
+        let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(point).map_err(invalid)?;
+        let signature = p256::ecdsa::Signature::from_der(signature).map_err(invalid)?;
+        key.verify_prehash(prehash, &signature).map_err(invalid)
+    } else if curve == SECP_384_R_1 {
+        let key = p384::ecdsa::VerifyingKey::from_sec1_bytes(point).map_err(invalid)?;
+        let signature = p384::ecdsa::Signature::from_der(signature).map_err(invalid)?;
+        key.verify_prehash(prehash, &signature).map_err(invalid)
+    } else {
+        Err(format!("unsupported curve {curve}"))
+    }
+}
+
+/// Verify a DER-encoded ECDSA signature.
+///
+/// Without `hash`, the hash that belongs to the curve is used, which is what
+/// Sigstore clients use for attestations and transparency log entries.
+fn verify_ecdsa(
+    key: &SubjectPublicKeyInfoOwned,
+    hash: Option<HashAlgorithm>,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), String> {
+    let hash = match hash {
+        Some(hash) => hash,
+        None if curve(key)? == SECP_384_R_1 => HashAlgorithm::Sha384,
+        None => HashAlgorithm::Sha256,
+    };
+    verify_ecdsa_prehash(key, &digest(hash, message), signature)
+}
+
+fn decode_base64(value: &Value, field: &str) -> Result<Vec<u8>, String> {
+    let text = value
+        .as_str()
+        .ok_or_else(|| format!("missing field {field}"))?;
+    BASE64
+        .decode(text)
+        .map_err(|e| format!("invalid base64 in {field}: {e}"))
+}
+
+/// Parse an integer that is either a JSON number or, as in protobuf JSON, a string.
+fn parse_int(value: &Value, field: &str) -> Result<i64, String> {
+    value
+        .as_i64()
+        .or_else(|| value.as_str().and_then(|text| text.parse().ok()))
+        .ok_or_else(|| format!("missing field {field}"))
+}
+
+fn parse_certificate(der: &[u8]) -> Result<Certificate, String> {
+    Certificate::from_der(der).map_err(|e| format!("invalid certificate: {e}"))
+}
+
+/// The period in which a key or certificate authority may be used.
+struct ValidFor {
+    start: u64,
+    end: Option<u64>,
+}
+
+impl ValidFor {
+    fn contains(&self, time: u64) -> bool {
+        self.start <= time && self.end.is_none_or(|end| time <= end)
+    }
+}
+
+fn parse_valid_for(value: &Value) -> Result<ValidFor, String> {
+    let timestamp = |field: &str| {
+        value[field].as_str().map(|text| {
+            crate::time::parse_timestamp(text).ok_or_else(|| format!("invalid timestamp {text}"))
+        })
+    };
+    let start = timestamp("start").ok_or("missing field validFor.start")??;
+    let end = timestamp("end").transpose()?;
+    Ok(ValidFor { start, end })
+}
+
+struct CertificateAuthority {
+    certificates: Vec<Certificate>,
+    valid_for: ValidFor,
+}
+
+struct TransparencyLog {
+    key_id: Vec<u8>,
+    key: SubjectPublicKeyInfoOwned,
+    valid_for: ValidFor,
+}
+
+/// The keys and certificates that are trusted to issue signing certificates
+/// (Fulcio) and to record signatures (Rekor).
+pub(crate) struct TrustedRoot {
+    certificate_authorities: Vec<CertificateAuthority>,
+    transparency_logs: Vec<TransparencyLog>,
+}
+
+pub(crate) fn parse_trusted_root(text: &str) -> Result<TrustedRoot, String> {
+    let root: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let mut certificate_authorities = vec![];
+    for ca in root["certificateAuthorities"].as_array().unwrap_or(&vec![]) {
+        let certificates = ca["certChain"]["certificates"]
+            .as_array()
+            .unwrap_or(&vec![])
+            .iter()
+            .map(|cert| parse_certificate(&decode_base64(&cert["rawBytes"], "rawBytes")?))
+            .collect::<Result<Vec<_>, String>>()?;
+        let valid_for = parse_valid_for(&ca["validFor"])?;
+        certificate_authorities.push(CertificateAuthority {
+            certificates,
+            valid_for,
+        });
+    }
+    let mut transparency_logs = vec![];
+    for tlog in root["tlogs"].as_array().unwrap_or(&vec![]) {
+        let key_id = decode_base64(&tlog["logId"]["keyId"], "logId.keyId")?;
+        let key = decode_base64(&tlog["publicKey"]["rawBytes"], "publicKey.rawBytes")?;
+        let key = SubjectPublicKeyInfoOwned::from_der(&key)
+            .map_err(|e| format!("invalid transparency log key: {e}"))?;
+        let valid_for = parse_valid_for(&tlog["publicKey"]["validFor"])?;
+        transparency_logs.push(TransparencyLog {
+            key_id,
+            key,
+            valid_for,
+        });
+    }
+    Ok(TrustedRoot {
+        certificate_authorities,
+        transparency_logs,
+    })
+}
+
+/// Load the trust root from `path` or use the embedded one.
+pub(crate) fn load_trusted_root(path: &Option<String>) -> TrustedRoot {
+    let text = match path {
+        Some(path) => std::fs::read_to_string(path)
+            .unwrap_or_else(|e| abort(&format!("Could not read trust root at {path}: {e}"))),
+        None => EMBEDDED_TRUSTED_ROOT.to_string(),
+    };
+    parse_trusted_root(&text).unwrap_or_else(|e| abort(&format!("Invalid trust root: {e}")))
+}
+
+/// Read a bundle from a path or URL.
+pub(crate) fn read_bundle(location: &str) -> Vec<u8> {
+    if crate::download::is_url(location) {
+        crate::download::download_file(location)
+    } else {
+        std::fs::read(location)
+            .unwrap_or_else(|e| abort(&format!("Could not read bundle at {location}: {e}")))
+    }
+}
+
+/// An entry in the Rekor transparency log together with its signed promise
+/// of inclusion.
+pub(crate) struct TlogEntry {
+    log_index: i64,
+    key_id: Vec<u8>,
+    integrated_time: i64,
+    signed_entry_timestamp: Vec<u8>,
+    /// The base64-encoded canonicalized body of the entry.
+    body: String,
+}
+
+impl TlogEntry {
+    fn body(&self) -> Result<Value, String> {
+        let body = BASE64
+            .decode(&self.body)
+            .map_err(|e| format!("invalid transparency log entry: {e}"))?;
+        serde_json::from_slice(&body).map_err(|e| format!("invalid transparency log entry: {e}"))
+    }
+}
+
+enum Content {
+    MessageSignature {
+        signature: Vec<u8>,
+    },
+    /// A signed in-toto attestation in a Dead Simple Signing Envelope.
+    Dsse {
+        payload_type: String,
+        payload: Vec<u8>,
+        signature: Vec<u8>,
+    },
+}
+
+/// A Sigstore bundle containing the signature, the signing certificate, and
+/// the transparency log entry.
+pub(crate) struct Bundle {
+    certificate: Certificate,
+    tlog_entries: Vec<TlogEntry>,
+    content: Content,
+}
+
+fn parse_tlog_entry(entry: &Value) -> Result<TlogEntry, String> {
+    Ok(TlogEntry {
+        log_index: parse_int(&entry["logIndex"], "logIndex")?,
+        key_id: decode_base64(&entry["logId"]["keyId"], "logId.keyId")?,
+        integrated_time: parse_int(&entry["integratedTime"], "integratedTime")?,
+        signed_entry_timestamp: decode_base64(
+            &entry["inclusionPromise"]["signedEntryTimestamp"],
+            "inclusionPromise.signedEntryTimestamp",
+        )?,
+        body: entry["canonicalizedBody"]
+            .as_str()
+            .ok_or("missing field canonicalizedBody")?
+            .to_string(),
+    })
+}
+
+/// Parse a bundle in the format written by `cosign sign-blob --bundle` before
+/// the Sigstore bundle format existed.
+fn parse_legacy_bundle(bundle: &Value) -> Result<Bundle, String> {
+    let pem = decode_base64(&bundle["cert"], "cert")?;
+    let certificate =
+        Certificate::from_pem(&pem).map_err(|e| format!("invalid certificate: {e}"))?;
+    let payload = &bundle["rekorBundle"]["Payload"];
+    let key_id = payload["logID"].as_str().ok_or("missing field logID")?;
+    let entry = TlogEntry {
+        log_index: parse_int(&payload["logIndex"], "logIndex")?,
+        key_id: hex::decode(key_id).map_err(|e| format!("invalid logID: {e}"))?,
+        integrated_time: parse_int(&payload["integratedTime"], "integratedTime")?,
+        signed_entry_timestamp: decode_base64(
+            &bundle["rekorBundle"]["SignedEntryTimestamp"],
+            "SignedEntryTimestamp",
+        )?,
+        body: payload["body"]
+            .as_str()
+            .ok_or("missing field body")?
+            .to_string(),
+    };
+    let signature = decode_base64(&bundle["base64Signature"], "base64Signature")?;
+    Ok(Bundle {
+        certificate,
+        tlog_entries: vec![entry],
+        content: Content::MessageSignature { signature },
+    })
+}
+
+/// Parse a Sigstore bundle (v0.1 to v0.3) or a legacy cosign bundle.
+pub(crate) fn parse_bundle(bytes: &[u8]) -> Result<Bundle, String> {
+    let bundle: Value = serde_json::from_slice(bytes).map_err(|e| e.to_string())?;
+    if bundle.get("rekorBundle").is_some() {
+        return parse_legacy_bundle(&bundle);
+    }
+    let material = &bundle["verificationMaterial"];
+    let raw_certificate = if material.get("certificate").is_some() {
+        &material["certificate"]["rawBytes"]
+    } else if material.get("x509CertificateChain").is_some() {
+        &material["x509CertificateChain"]["certificates"][0]["rawBytes"]
+    } else {
+        return Err(
+            "the bundle does not contain a certificate; only keyless signatures are supported"
+                .to_string(),
+        );
+    };
+    let certificate = parse_certificate(&decode_base64(raw_certificate, "certificate")?)?;
+    let tlog_entries = material["tlogEntries"]
+        .as_array()
+        .unwrap_or(&vec![])
+        .iter()
+        .map(parse_tlog_entry)
+        .collect::<Result<Vec<_>, String>>()?;
+    let content = if let Some(message) = bundle.get("messageSignature") {
+        let signature = decode_base64(&message["signature"], "messageSignature.signature")?;
+        Content::MessageSignature { signature }
+    } else if let Some(envelope) = bundle.get("dsseEnvelope") {
+        let payload_type = envelope["payloadType"]
+            .as_str()
+            .ok_or("missing field dsseEnvelope.payloadType")?
+            .to_string();
+        let payload = decode_base64(&envelope["payload"], "dsseEnvelope.payload")?;
+        let signature = decode_base64(
+            &envelope["signatures"][0]["sig"],
+            "dsseEnvelope.signatures.sig",
+        )?;
+        Content::Dsse {
+            payload_type,
+            payload,
+            signature,
+        }
+    } else {
+        return Err("the bundle does not contain a signature".to_string());
+    };
+    Ok(Bundle {
+        certificate,
+        tlog_entries,
+        content,
+    })
+}
+
+/// Verify the signed entry timestamp, which is Rekor's promise that the entry
+/// was added to the log at `integrated_time`.
+fn verify_tlog_entry(entry: &TlogEntry, root: &TrustedRoot) -> Result<u64, String> {
+    let tlog = root
+        .transparency_logs
+        .iter()
+        .find(|tlog| tlog.key_id == entry.key_id)
+        .ok_or("the transparency log entry is from an unknown log")?;
+    let integrated_time =
+        u64::try_from(entry.integrated_time).map_err(|_| "invalid integrated time")?;
+    if !tlog.valid_for.contains(integrated_time) {
+        return Err("the transparency log key was not valid at the integrated time".to_string());
+    }
+    // The canonical JSON with keys in lexicographic order and without spaces.
+    let payload = format!(
+        r#"{{"body":"{}","integratedTime":{},"logID":"{}","logIndex":{}}}"#,
+        entry.body,
+        entry.integrated_time,
+        hex::encode(&entry.key_id),
+        entry.log_index
+    );
+    verify_ecdsa(
+        &tlog.key,
+        None,
+        payload.as_bytes(),
+        &entry.signed_entry_timestamp,
+    )
+    .map_err(|e| format!("the signed entry timestamp is invalid: {e}"))?;
+    Ok(integrated_time)
+}
+
+fn hash_algorithm(oid: ObjectIdentifier) -> Result<HashAlgorithm, String> {
+    if oid == ECDSA_WITH_SHA_256 {
+        Ok(HashAlgorithm::Sha256)
+    } else if oid == ECDSA_WITH_SHA_384 {
+        Ok(HashAlgorithm::Sha384)
+    } else {
+        Err(format!("unsupported signature algorithm {oid}"))
+    }
+}
+
+/// Verify that the certificate was issued by a trusted certificate authority
+/// and was valid at `time`.
+///
+/// Fulcio certificates are only valid for ten minutes, so `time` is the time
+/// at which the signature was recorded in the transparency log.
+fn verify_certificate(
+    certificate: &Certificate,
+    root: &TrustedRoot,
+    time: u64,
+) -> Result<(), String> {
+    let tbs = &certificate.tbs_certificate;
+    let not_before = tbs.validity.not_before.to_unix_duration().as_secs();
+    let not_after = tbs.validity.not_after.to_unix_duration().as_secs();
+    if time < not_before || not_after < time {
+        return Err("the certificate was not valid when the signature was recorded".to_string());
+    }
+    let hash = hash_algorithm(certificate.signature_algorithm.oid)?;
+    let message = tbs.to_der().map_err(|e| e.to_string())?;
+    let signature = certificate
+        .signature
+        .as_bytes()
+        .ok_or("invalid certificate signature")?;
+    let issued = root
+        .certificate_authorities
+        .iter()
+        .filter(|ca| ca.valid_for.contains(time))
+        .flat_map(|ca| &ca.certificates)
+        .filter(|issuer| issuer.tbs_certificate.subject == tbs.issuer)
+        .any(|issuer| {
+            let key = &issuer.tbs_certificate.subject_public_key_info;
+            verify_ecdsa(key, Some(hash), &message, signature).is_ok()
+        });
+    if !issued {
+        return Err(
+            "the certificate was not issued by a trusted certificate authority".to_string(),
+        );
+    }
+    let code_signing = tbs
+        .get::<ExtendedKeyUsage>()
+        .map_err(|e| e.to_string())?
+        .is_some_and(|(_, usage)| usage.0.contains(&ID_KP_CODE_SIGNING));
+    if !code_signing {
+        return Err("the certificate is not valid for code signing".to_string());
+    }
+    Ok(())
+}
+
+/// The identities in the subject alternative name, such as the workflow URL
+/// for GitHub Actions or the email address for interactive signing.
+fn certificate_identities(certificate: &Certificate) -> Vec<String> {
+    let Ok(Some((_, san))) = certificate.tbs_certificate.get::<SubjectAltName>() else {
+        return vec![];
+    };
+    san.0
+        .iter()
+        .filter_map(|name| match name {
+            GeneralName::UniformResourceIdentifier(uri) => Some(uri.to_string()),
+            GeneralName::Rfc822Name(email) => Some(email.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The OIDC issuer that authenticated the signer, such as
+/// `https://token.actions.githubusercontent.com`.
+fn certificate_issuer(certificate: &Certificate) -> Option<String> {
+    let extensions = certificate.tbs_certificate.extensions.as_ref()?;
+    let value = |oid| {
+        extensions
+            .iter()
+            .find(|extension| extension.extn_id == oid)
+            .map(|extension| extension.extn_value.as_bytes())
+    };
+    if let Some(value) = value(OID_ISSUER_V2) {
+        let issuer = Utf8StringRef::from_der(value).ok()?;
+        return Some(issuer.as_str().to_string());
+    }
+    value(OID_ISSUER_V1).and_then(|value| String::from_utf8(value.to_vec()).ok())
+}
+
+/// Verify that the certificate was issued to `identity` by `issuer`.
+///
+/// Like `cosign --certificate-identity-regexp`, the identity is a regular
+/// expression that may match any part of the identity in the certificate.
+fn verify_identity(certificate: &Certificate, identity: &str, issuer: &str) -> Result<(), String> {
+    let regex = Regex::new(identity).map_err(|e| format!("invalid identity regex: {e}"))?;
+    let identities = certificate_identities(certificate);
+    if !identities.iter().any(|current| regex.is_match(current)) {
+        return Err(format!(
+            "the certificate identity {} does not match {identity}",
+            identities.join(", ")
+        ));
+    }
+    match certificate_issuer(certificate) {
+        Some(current) if current == issuer => Ok(()),
+        Some(current) => Err(format!(
+            "the certificate issuer {current} does not match {issuer}"
+        )),
+        None => Err("the certificate does not contain an issuer".to_string()),
+    }
+}
+
+/// Verify the certificate, identity, and transparency log entry of the bundle.
+///
+/// Returns the transparency log entry so that the caller can check that it
+/// refers to the signed content.
+fn verify_bundle<'a>(
+    bundle: &'a Bundle,
+    root: &TrustedRoot,
+    identity: &str,
+    issuer: &str,
+) -> Result<&'a TlogEntry, String> {
+    let entry = bundle
+        .tlog_entries
+        .first()
+        .ok_or("the bundle does not contain a transparency log entry")?;
+    let time = verify_tlog_entry(entry, root)?;
+    verify_certificate(&bundle.certificate, root, time)?;
+    verify_identity(&bundle.certificate, identity, issuer)?;
+    Ok(entry)
+}
+
+/// Whether the PEM-encoded certificate in the transparency log entry is the
+/// certificate from the bundle.
+fn same_certificate(pem: &[u8], certificate: &Certificate) -> bool {
+    Certificate::from_pem(pem).is_ok_and(|current| &current == certificate)
+}
+
+/// Verify a bundle with a signature over the artifact with the given SHA-256 digest.
+///
+/// The signature is over the digest, so the artifact itself is not needed.
+pub(crate) fn verify_artifact(
+    bundle: &Bundle,
+    sha256: &[u8],
+    root: &TrustedRoot,
+    identity: &str,
+    issuer: &str,
+) -> Result<(), String> {
+    let Content::MessageSignature { signature } = &bundle.content else {
+        return Err("the bundle contains an attestation instead of a signature".to_string());
+    };
+    let entry = verify_bundle(bundle, root, identity, issuer)?;
+    let key = &bundle.certificate.tbs_certificate.subject_public_key_info;
+    verify_ecdsa_prehash(key, sha256, signature)
+        .map_err(|e| format!("the signature does not match the artifact: {e}"))?;
+    let body = entry.body()?;
+    if body["kind"] != "hashedrekord" {
+        return Err(format!(
+            "unexpected transparency log entry kind {}",
+            body["kind"]
+        ));
+    }
+    let spec = &body["spec"];
+    let hash = hex::encode(sha256);
+    if spec["data"]["hash"]["algorithm"] != "sha256" || spec["data"]["hash"]["value"] != hash {
+        return Err("the transparency log entry is for a different artifact".to_string());
+    }
+    let logged_signature = decode_base64(&spec["signature"]["content"], "signature.content")?;
+    let logged_certificate = decode_base64(
+        &spec["signature"]["publicKey"]["content"],
+        "signature.publicKey.content",
+    )?;
+    if &logged_signature != signature || !same_certificate(&logged_certificate, &bundle.certificate)
+    {
+        return Err("the transparency log entry is for a different signature".to_string());
+    }
+    Ok(())
+}
+
+/// The pre-authentication encoding that is signed in a DSSE envelope.
+fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut encoded = format!(
+        "DSSEv1 {} {payload_type} {} ",
+        payload_type.len(),
+        payload.len()
+    )
+    .into_bytes();
+    encoded.extend_from_slice(payload);
+    encoded
+}
+
+/// Whether the `dsse` or `intoto` transparency log entry is for the envelope.
+fn logged_envelope(
+    body: &Value,
+    payload: &[u8],
+    signature: &[u8],
+    certificate: &Certificate,
+) -> Result<bool, String> {
+    let payload_hash = hex::encode(sha2::Sha256::digest(payload));
+    let (spec, signatures, signature_field, key_field) = match body["kind"].as_str() {
+        Some("dsse") => (
+            &body["spec"],
+            &body["spec"]["signatures"],
+            "signature",
+            "verifier",
+        ),
+        Some("intoto") => (
+            &body["spec"]["content"],
+            &body["spec"]["content"]["envelope"]["signatures"],
+            "sig",
+            "publicKey",
+        ),
+        _ => {
+            return Err(format!(
+                "unexpected transparency log entry kind {}",
+                body["kind"]
+            ))
+        }
+    };
+    if spec["payloadHash"]["algorithm"] != "sha256" || spec["payloadHash"]["value"] != payload_hash
+    {
+        return Ok(false);
+    }
+    let logged = signatures.as_array().map(Vec::as_slice).unwrap_or(&[]);
+    Ok(logged.iter().any(|logged| {
+        let Ok(logged_signature) = decode_base64(&logged[signature_field], signature_field) else {
+            return false;
+        };
+        // The `intoto` entries contain the base64-encoded signature again encoded as base64.
+        let same_signature = logged_signature == signature
+            || BASE64
+                .decode(&logged_signature)
+                .is_ok_and(|decoded| decoded == signature);
+        let same_key = decode_base64(&logged[key_field], key_field)
+            .is_ok_and(|pem| same_certificate(&pem, certificate));
+        same_signature && same_key
+    }))
+}
+
+/// Verify a bundle with a signed in-toto attestation and return the in-toto
+/// statement.
+pub(crate) fn verify_attestation(
+    bundle: &Bundle,
+    root: &TrustedRoot,
+    identity: &str,
+    issuer: &str,
+) -> Result<Value, String> {
+    let Content::Dsse {
+        payload_type,
+        payload,
+        signature,
+    } = &bundle.content
+    else {
+        return Err("the bundle contains a signature instead of an attestation".to_string());
+    };
+    if payload_type != "application/vnd.in-toto+json" {
+        return Err(format!("unexpected payload type {payload_type}"));
+    }
+    let entry = verify_bundle(bundle, root, identity, issuer)?;
+    let key = &bundle.certificate.tbs_certificate.subject_public_key_info;
+    verify_ecdsa(key, None, &pae(payload_type, payload), signature)
+        .map_err(|e| format!("the signature does not match the attestation: {e}"))?;
+    if !logged_envelope(&entry.body()?, payload, signature, &bundle.certificate)? {
+        return Err("the transparency log entry is for a different attestation".to_string());
+    }
+    serde_json::from_slice(payload).map_err(|e| format!("invalid in-toto statement: {e}"))
+}
+
+#[test]
+fn test_embedded_trusted_root() {
+    let root = parse_trusted_root(EMBEDDED_TRUSTED_ROOT).unwrap();
+    assert_eq!(root.certificate_authorities.len(), 2);
+    assert_eq!(root.certificate_authorities[1].certificates.len(), 2);
+    assert!(root.certificate_authorities[1].valid_for.end.is_none());
+    assert_eq!(root.transparency_logs.len(), 1);
+    assert_eq!(
+        hex::encode(&root.transparency_logs[0].key_id),
+        "c0d23d6ad406973f9559f3ba2d1ca01f84147d8ffc5b8445c224f98b9591801d"
+    );
+}
+
+#[cfg(test)]
+fn test_certificate(
+    subject: &str,
+    issuer: &str,
+    key: SubjectPublicKeyInfoOwned,
+    validity: (u64, u64),
+    extensions: Vec<x509_cert::ext::Extension>,
+    signing_key: &p384::ecdsa::SigningKey,
+) -> Certificate {
+    use p384::ecdsa::signature::Signer;
+    use std::str::FromStr;
+    use x509_cert::der::asn1::BitString;
+    use x509_cert::name::Name;
+    use x509_cert::spki::AlgorithmIdentifierOwned;
+    use x509_cert::time::Time;
+    use x509_cert::time::Validity;
+    let time = |secs| Time::try_from(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs));
+    let algorithm = AlgorithmIdentifierOwned {
+        oid: ECDSA_WITH_SHA_384,
+        parameters: None,
+    };
+    let tbs_certificate = x509_cert::TbsCertificate {
+        version: x509_cert::Version::V3,
+        serial_number: x509_cert::serial_number::SerialNumber::new(&[1]).unwrap(),
+        signature: algorithm.clone(),
+        issuer: Name::from_str(issuer).unwrap(),
+        validity: Validity {
+            not_before: time(validity.0).unwrap(),
+            not_after: time(validity.1).unwrap(),
+        },
+        subject: if subject.is_empty() {
+            Name::default()
+        } else {
+            Name::from_str(subject).unwrap()
+        },
+        subject_public_key_info: key,
+        issuer_unique_id: None,
+        subject_unique_id: None,
+        extensions: Some(extensions),
+    };
+    let signature: p384::ecdsa::DerSignature = signing_key.sign(&tbs_certificate.to_der().unwrap());
+    Certificate {
+        tbs_certificate,
+        signature_algorithm: algorithm,
+        signature: BitString::from_bytes(signature.as_bytes()).unwrap(),
+    }
+}
+
+/// Create a trust root and a bundle for `artifact` with a fake certificate
+/// authority and transparency log.
+#[cfg(test)]
+fn test_bundle(
+    artifact: &[u8],
+    identity: &str,
+    issuer: &str,
+    integrated_time: u64,
+) -> (String, Value) {
+    use p256::ecdsa::signature::Signer;
+    use x509_cert::der::asn1::Ia5String;
+    use x509_cert::der::asn1::OctetString;
+    use x509_cert::der::EncodePem;
+    use x509_cert::ext::Extension;
+    let spki = |key: &p256::ecdsa::SigningKey| {
+        SubjectPublicKeyInfoOwned::from_key(*key.verifying_key()).unwrap()
+    };
+    let extension = |extn_id, value: Vec<u8>| Extension {
+        extn_id,
+        critical: false,
+        extn_value: OctetString::new(value).unwrap(),
+    };
+    let ca_key = p384::ecdsa::SigningKey::from_slice(&[7; 48]).unwrap();
+    let leaf_key = p256::ecdsa::SigningKey::from_slice(&[3; 32]).unwrap();
+    let rekor_key = p256::ecdsa::SigningKey::from_slice(&[5; 32]).unwrap();
+
+    let ca_name = "CN=sigstore-intermediate,O=sigstore.dev";
+    let ca_spki = SubjectPublicKeyInfoOwned::from_key(*ca_key.verifying_key()).unwrap();
+    let ca = test_certificate(
+        ca_name,
+        ca_name,
+        ca_spki,
+        (1577836800, 2208988800),
+        vec![],
+        &ca_key,
+    );
+    let not_before = 1744124706;
+    let san = SubjectAltName(vec![GeneralName::UniformResourceIdentifier(
+        Ia5String::new(identity).unwrap(),
+    )]);
+    let extensions = vec![
+        extension(
+            x509_cert::der::oid::db::rfc5280::ID_CE_SUBJECT_ALT_NAME,
+            san.to_der().unwrap(),
+        ),
+        extension(
+            OID_ISSUER_V2,
+            Utf8StringRef::new(issuer).unwrap().to_der().unwrap(),
+        ),
+        extension(
+            x509_cert::der::oid::db::rfc5280::ID_CE_EXT_KEY_USAGE,
+            ExtendedKeyUsage(vec![ID_KP_CODE_SIGNING]).to_der().unwrap(),
+        ),
+    ];
+    let validity = (not_before, not_before + 600);
+    let leaf = test_certificate("", ca_name, spki(&leaf_key), validity, extensions, &ca_key);
+
+    let signature: p256::ecdsa::DerSignature = leaf_key.sign(artifact);
+    let leaf_pem = leaf.to_pem(x509_cert::der::pem::LineEnding::LF).unwrap();
+    let body = serde_json::json!({
+        "apiVersion": "0.0.1",
+        "kind": "hashedrekord",
+        "spec": {
+            "data": { "hash": { "algorithm": "sha256", "value": hex::encode(sha2::Sha256::digest(artifact)) } },
+            "signature": {
+                "content": BASE64.encode(signature.as_bytes()),
+                "publicKey": { "content": BASE64.encode(leaf_pem) },
+            },
+        },
+    });
+    let body = BASE64.encode(body.to_string());
+    let rekor_spki = spki(&rekor_key).to_der().unwrap();
+    let key_id = sha2::Sha256::digest(&rekor_spki);
+    let payload = format!(
+        r#"{{"body":"{body}","integratedTime":{integrated_time},"logID":"{}","logIndex":42}}"#,
+        hex::encode(key_id)
+    );
+    let set: p256::ecdsa::DerSignature = rekor_key.sign(payload.as_bytes());
+
+    let root = serde_json::json!({
+        "certificateAuthorities": [{
+            "certChain": { "certificates": [{ "rawBytes": BASE64.encode(ca.to_der().unwrap()) }] },
+            "validFor": { "start": "2020-01-01T00:00:00Z" },
+        }],
+        "tlogs": [{
+            "publicKey": {
+                "rawBytes": BASE64.encode(&rekor_spki),
+                "validFor": { "start": "2020-01-01T00:00:00Z" },
+            },
+            "logId": { "keyId": BASE64.encode(key_id) },
+        }],
+    });
+    let bundle = serde_json::json!({
+        "mediaType": "application/vnd.dev.sigstore.bundle.v0.3+json",
+        "verificationMaterial": {
+            "certificate": { "rawBytes": BASE64.encode(leaf.to_der().unwrap()) },
+            "tlogEntries": [{
+                "logIndex": "42",
+                "logId": { "keyId": BASE64.encode(key_id) },
+                "kindVersion": { "kind": "hashedrekord", "version": "0.0.1" },
+                "integratedTime": integrated_time.to_string(),
+                "inclusionPromise": { "signedEntryTimestamp": BASE64.encode(set.as_bytes()) },
+                "canonicalizedBody": body,
+            }],
+        },
+        "messageSignature": {
+            "messageDigest": { "algorithm": "SHA2_256", "digest": BASE64.encode(sha2::Sha256::digest(artifact)) },
+            "signature": BASE64.encode(signature.as_bytes()),
+        },
+    });
+    (root.to_string(), bundle)
+}
+
+#[test]
+fn test_verify_artifact() {
+    let identity = "https://github.com/owner/repo/.github/workflows/release.yml@refs/tags/v1.0.0";
+    let issuer = "https://token.actions.githubusercontent.com";
+    let (root, bundle) = test_bundle(b"artifact", identity, issuer, 1744124766);
+    let root = parse_trusted_root(&root).unwrap();
+    let verify = |bundle: &Value, artifact: &[u8], identity: &str, issuer: &str| {
+        let bundle = parse_bundle(bundle.to_string().as_bytes()).unwrap();
+        let digest = sha2::Sha256::digest(artifact);
+        verify_artifact(&bundle, &digest, &root, identity, issuer)
+    };
+    assert_eq!(verify(&bundle, b"artifact", identity, issuer), Ok(()));
+    let pattern = "^https://github.com/owner/repo/.github/workflows/release.yml@refs/tags/";
+    assert_eq!(verify(&bundle, b"artifact", pattern, issuer), Ok(()));
+
+    let result = verify(&bundle, b"tampered", identity, issuer);
+    assert!(result.unwrap_err().contains("does not match the artifact"));
+    let result = verify(&bundle, b"artifact", "github.com/other/repo", issuer);
+    assert!(result.unwrap_err().contains("identity"));
+    let result = verify(
+        &bundle,
+        b"artifact",
+        identity,
+        "https://accounts.google.com",
+    );
+    assert!(result.unwrap_err().contains("issuer"));
+
+    let mut tampered = bundle.clone();
+    tampered["verificationMaterial"]["tlogEntries"][0]["integratedTime"] = "1744124767".into();
+    let result = verify(&tampered, b"artifact", identity, issuer);
+    assert!(result.unwrap_err().contains("signed entry timestamp"));
+
+    let other_root = parse_trusted_root(EMBEDDED_TRUSTED_ROOT).unwrap();
+    let parsed = parse_bundle(bundle.to_string().as_bytes()).unwrap();
+    let result = verify_artifact(
+        &parsed,
+        &sha2::Sha256::digest(b"artifact"),
+        &other_root,
+        identity,
+        issuer,
+    );
+    assert!(result.unwrap_err().contains("unknown log"));
+}
+
+#[test]
+fn test_verify_artifact_expired_certificate() {
+    let identity = "https://github.com/owner/repo/.github/workflows/release.yml@refs/tags/v1.0.0";
+    let issuer = "https://token.actions.githubusercontent.com";
+    // Recorded after the ten-minute validity of the certificate.
+    let (root, bundle) = test_bundle(b"artifact", identity, issuer, 1744125706);
+    let root = parse_trusted_root(&root).unwrap();
+    let bundle = parse_bundle(bundle.to_string().as_bytes()).unwrap();
+    let result = verify_artifact(
+        &bundle,
+        &sha2::Sha256::digest(b"artifact"),
+        &root,
+        identity,
+        issuer,
+    );
+    assert!(result
+        .unwrap_err()
+        .contains("not valid when the signature was recorded"));
+}
+
+#[test]
+fn test_parse_legacy_bundle() {
+    use x509_cert::der::EncodePem;
+    let identity = "user@example.com";
+    let issuer = "https://github.com/login/oauth";
+    let (root, bundle) = test_bundle(b"artifact", identity, issuer, 1744124766);
+    let root = parse_trusted_root(&root).unwrap();
+    let material = &bundle["verificationMaterial"];
+    let entry = &material["tlogEntries"][0];
+    let certificate = decode_base64(&material["certificate"]["rawBytes"], "").unwrap();
+    let certificate = parse_certificate(&certificate).unwrap();
+    let pem = certificate
+        .to_pem(x509_cert::der::pem::LineEnding::LF)
+        .unwrap();
+    let key_id = decode_base64(&entry["logId"]["keyId"], "").unwrap();
+    let legacy = serde_json::json!({
+        "base64Signature": bundle["messageSignature"]["signature"],
+        "cert": BASE64.encode(pem),
+        "rekorBundle": {
+            "SignedEntryTimestamp": entry["inclusionPromise"]["signedEntryTimestamp"],
+            "Payload": {
+                "body": entry["canonicalizedBody"],
+                "integratedTime": 1744124766,
+                "logIndex": 42,
+                "logID": hex::encode(key_id),
+            },
+        },
+    });
+    let legacy = parse_bundle(legacy.to_string().as_bytes()).unwrap();
+    assert_eq!(
+        verify_artifact(
+            &legacy,
+            &sha2::Sha256::digest(b"artifact"),
+            &root,
+            identity,
+            issuer
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_verify_attestation() {
+    // A bundle that was created by cosign in GitHub Actions.
+    let bundle = include_bytes!("../tests/data/attestation.sigstore.json");
+    let bundle = parse_bundle(bundle).unwrap();
+    let root = parse_trusted_root(EMBEDDED_TRUSTED_ROOT).unwrap();
+    let identity =
+        "^https://github.com/kubewarden/kubewarden-controller/.github/workflows/release.yml@";
+    let issuer = "https://token.actions.githubusercontent.com";
+    let statement = verify_attestation(&bundle, &root, identity, issuer).unwrap();
+    assert_eq!(
+        statement["subject"][0]["digest"]["sha256"],
+        "c811d58de79c92f03214e63aa339484e488d694ae8a6283b5f3f17a9faf50172"
+    );
+
+    let other = "^https://github.com/attacker/kubewarden-controller/";
+    let result = verify_attestation(&bundle, &root, other, issuer);
+    assert!(result.unwrap_err().contains("identity"));
+    let result = verify_artifact(
+        &bundle,
+        &sha2::Sha256::digest(b"artifact"),
+        &root,
+        identity,
+        issuer,
+    );
+    assert!(result
+        .unwrap_err()
+        .contains("attestation instead of a signature"));
+
+    let Content::Dsse {
+        payload_type,
+        payload,
+        signature,
+    } = bundle.content
+    else {
+        panic!("expected an attestation");
+    };
+    let mut tampered = payload.clone();
+    tampered[0] = b' ';
+    let bundle = Bundle {
+        content: Content::Dsse {
+            payload_type,
+            payload: tampered,
+            signature,
+        },
+        ..bundle
+    };
+    let result = verify_attestation(&bundle, &root, identity, issuer);
+    assert!(result
+        .unwrap_err()
+        .contains("does not match the attestation"));
+}
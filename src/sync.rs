@@ -0,0 +1,28 @@
+use crate::abort;
+use crate::manifest::read_manifest;
+use crate::manifest::tool_label;
+use crate::SyncArgs;
+use std::path::Path;
+
+/// Install all tools listed in the manifest.
+///
+/// Stops at the first tool that fails to install, for example due to a SHA-256
+/// mismatch, so that the whole sync fails.
+pub(crate) fn run(args: &SyncArgs) {
+    let path = Path::new(&args.manifest);
+    let manifest = read_manifest(path);
+    if manifest.tools.is_empty() {
+        abort(&format!("No tools found in {}", path.display()));
+    }
+    let n = manifest.tools.len();
+    for (i, tool) in manifest.tools.iter().enumerate() {
+        tracing::info!("Installing {} ({}/{n})", tool_label(tool), i + 1);
+        let mut tool = tool.clone();
+        tool.gh_token = args.gh_token.clone();
+        tool.lockfile = Some(args.lockfile.clone());
+        tool.cache_dir = args.cache_dir.clone();
+        tool.offline = args.offline;
+        crate::install::run(&tool);
+    }
+    tracing::info!("Installed {n} tools from {}", path.display());
+}
@@ -0,0 +1,82 @@
+use semver::Version;
+use semver::VersionReq;
+use serde_json::Value;
+
+/// Whether the tag is a semver requirement such as `^1.31` or `~0.13`.
+pub(crate) fn is_version_req(tag: &str) -> bool {
+    tag.starts_with(['^', '~', '=', '>', '<', '*'])
+}
+
+/// Parse the version from a tag.
+///
+/// Handles tags with and without a `v` prefix, such as `v1.31.1` (typos),
+/// `1.40.0` (just), and `cargo-audit/v0.22.0` (rustsec). Missing minor and
+/// patch versions are assumed to be zero.
+pub(crate) fn parse_tag_version(tag: &str) -> Option<Version> {
+    let tag = tag.rsplit('/').next().unwrap();
+    let tag = tag.strip_prefix('v').unwrap_or(tag);
+    if let Ok(version) = Version::parse(tag) {
+        return Some(version);
+    }
+    let n = tag.split('.').count();
+    if n < 3 && !tag.contains('-') {
+        let padded = format!("{tag}{}", ".0".repeat(3 - n));
+        return Version::parse(&padded).ok();
+    }
+    None
+}
+
+/// Return the release with the highest version that matches the requirement.
+///
+/// Drafts and prereleases are skipped.
+pub(crate) fn select_release<'a>(releases: &'a [Value], req: &VersionReq) -> Option<&'a Value> {
+    releases
+        .iter()
+        .filter(|release| !release["draft"].as_bool().unwrap_or(false))
+        .filter(|release| !release["prerelease"].as_bool().unwrap_or(false))
+        .filter_map(|release| {
+            let tag = release["tag_name"].as_str()?;
+            let version = parse_tag_version(tag)?;
+            req.matches(&version).then_some((version, release))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, release)| release)
+}
+
+#[test]
+fn test_parse_tag_version() {
+    let version = |tag: &str| parse_tag_version(tag).map(|v| v.to_string());
+    assert_eq!(version("v1.31.1"), Some("1.31.1".to_string()));
+    assert_eq!(version("1.40.0"), Some("1.40.0".to_string()));
+    assert_eq!(version("0.18.2"), Some("0.18.2".to_string()));
+    assert_eq!(version("cargo-audit/v0.22.0"), Some("0.22.0".to_string()));
+    assert_eq!(version("v0.13"), Some("0.13.0".to_string()));
+    assert_eq!(version("v0.13.0-rc1"), Some("0.13.0-rc1".to_string()));
+    assert_eq!(version("nightly"), None);
+}
+
+#[test]
+fn test_select_release() {
+    let releases = serde_json::json!([
+        { "tag_name": "v1.32.0-rc.1", "draft": false, "prerelease": true },
+        { "tag_name": "v2.0.0", "draft": false, "prerelease": false },
+        { "tag_name": "v1.31.2", "draft": true, "prerelease": false },
+        { "tag_name": "v1.31.1", "draft": false, "prerelease": false },
+        { "tag_name": "v1.31.0", "draft": false, "prerelease": false },
+        { "tag_name": "v1.30.3", "draft": false, "prerelease": false },
+    ]);
+    let releases = releases.as_array().unwrap();
+    let select = |req: &str| {
+        let req = VersionReq::parse(req).unwrap();
+        select_release(releases, &req).map(|release| release["tag_name"].as_str().unwrap())
+    };
+    assert_eq!(select("^1.31"), Some("v1.31.1"));
+    assert_eq!(select("~1.30"), Some("v1.30.3"));
+    assert_eq!(select(">=1"), Some("v2.0.0"));
+    assert_eq!(select("^3"), None);
+
+    assert!(is_version_req("^1.31"));
+    assert!(is_version_req("~0.13"));
+    assert!(!is_version_req("v1.31.1"));
+    assert!(!is_version_req("latest"));
+}
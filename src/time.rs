@@ -0,0 +1,82 @@
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+/// The current time in seconds since the Unix epoch.
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Format the current time as an RFC 3339 timestamp in UTC.
+pub(crate) fn now() -> String {
+    format_timestamp(unix_now())
+}
+
+/// Format seconds since the Unix epoch as an RFC 3339 timestamp in UTC.
+///
+/// Uses the days-to-civil algorithm by Howard Hinnant to avoid a dependency.
+pub(crate) fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    let (hour, minute, second) = (rem / 3600, (rem % 3600) / 60, rem % 60);
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z")
+}
+
+/// Parse an RFC 3339 timestamp in UTC such as `2021-01-12T11:53:27.000Z`.
+///
+/// Fractional seconds are ignored. Uses the days-from-civil algorithm by
+/// Howard Hinnant.
+pub(crate) fn parse_timestamp(text: &str) -> Option<u64> {
+    let text = text.strip_suffix('Z')?;
+    let (date, time) = text.split_once('T')?;
+    let time = time.split('.').next()?;
+    let date = date
+        .split('-')
+        .map(|part| part.parse::<i64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let time = time
+        .split(':')
+        .map(|part| part.parse::<i64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let (&[year, month, day], &[hour, minute, second]) = (date.as_slice(), time.as_slice()) else {
+        return None;
+    };
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second;
+    u64::try_from(secs).ok()
+}
+
+#[test]
+fn test_format_timestamp() {
+    assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+    assert_eq!(format_timestamp(951782400), "2000-02-29T00:00:00Z");
+    assert_eq!(format_timestamp(1744124706), "2025-04-08T15:05:06Z");
+}
+
+#[test]
+fn test_parse_timestamp() {
+    assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+    assert_eq!(parse_timestamp("2000-02-29T00:00:00Z"), Some(951782400));
+    assert_eq!(
+        parse_timestamp("2021-01-12T11:53:27.000Z"),
+        Some(1610452407)
+    );
+    assert_eq!(parse_timestamp("2021-01-12"), None);
+}
@@ -0,0 +1,297 @@
+use std::path::Path;
+use std::path::PathBuf;
+use tempfile::NamedTempFile;
+use tempfile::TempDir;
+
+/// The prefix of the temporary files in the install directory.
+static TEMP_PREFIX: &str = ".jas-";
+
+/// The name of the staged or replaced directory inside its temporary
+/// directory.
+static DIR_NAME: &str = "dir";
+
+/// Executables that are written to temporary files next to their destination
+/// and moved into place together.
+///
+/// Writing to a temporary file and renaming it means that an interrupted
+/// install never leaves a truncated executable behind, and that a running
+/// executable can be replaced without `ETXTBSY`. Directories, such as the
+/// unpacked contents of an archive, are staged in the same way. Uncommitted
+/// temporary files are removed on drop.
+pub(crate) struct Transaction {
+    staged: Vec<(Staged, PathBuf)>,
+}
+
+/// A file or directory that replaces its destination on commit.
+enum Staged {
+    File(NamedTempFile),
+    /// A temporary directory that holds the new directory as `DIR_NAME`.
+    Dir(TempDir),
+}
+
+/// The previous version of a destination, which is removed on drop.
+enum Backup {
+    /// A copy of the executable that was replaced.
+    File(NamedTempFile<()>),
+    /// A temporary directory that holds the replaced entry as `DIR_NAME`.
+    Dir(TempDir),
+}
+
+fn temp_builder() -> tempfile::Builder<'static, 'static> {
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(TEMP_PREFIX);
+    builder
+}
+
+fn parent(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// Keep the current version of `dst` so that it can be restored.
+///
+/// A hard link is used when possible since it is cheap and leaves `dst` in
+/// place until it is atomically replaced.
+fn backup(dst: &Path) -> Result<Option<Backup>, String> {
+    if !dst.is_file() {
+        return Ok(None);
+    }
+    let dir = parent(dst);
+    temp_builder()
+        .make_in(dir, |path| std::fs::hard_link(dst, path))
+        .or_else(|_| temp_builder().make_in(dir, |path| std::fs::copy(dst, path).map(|_| ())))
+        .map(|backup| Some(Backup::File(backup)))
+        .map_err(|e| format!("could not back up {}: {e}", dst.display()))
+}
+
+/// Move the current version of `dst` aside so that a directory can take its
+/// place.
+fn backup_dir(dst: &Path) -> Result<Option<Backup>, String> {
+    if std::fs::symlink_metadata(dst).is_err() {
+        return Ok(None);
+    }
+    temp_builder()
+        .tempdir_in(parent(dst))
+        .and_then(|backup| {
+            std::fs::rename(dst, backup.path().join(DIR_NAME))?;
+            Ok(Some(Backup::Dir(backup)))
+        })
+        .map_err(|e| format!("could not back up {}: {e}", dst.display()))
+}
+
+fn remove(path: &Path) -> std::io::Result<()> {
+    if std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir()) {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Restore the replaced executables and directories in reverse order.
+fn rollback(committed: Vec<(PathBuf, Option<Backup>)>) {
+    for (dst, backup) in committed.into_iter().rev() {
+        let result = match backup {
+            Some(Backup::File(backup)) => backup.persist(&dst).map(|_| ()).map_err(|e| e.error),
+            Some(Backup::Dir(backup)) => {
+                // The new directory is not in place if its rename failed.
+                let _ = remove(&dst);
+                std::fs::rename(backup.path().join(DIR_NAME), &dst)
+            }
+            None => remove(&dst),
+        };
+        match result {
+            Ok(()) => tracing::info!("Restored the previous state of {}", dst.display()),
+            Err(e) => tracing::warn!("Could not restore {}: {e}", dst.display()),
+        }
+    }
+}
+
+impl Transaction {
+    pub(crate) fn new() -> Transaction {
+        Transaction { staged: vec![] }
+    }
+
+    /// Create a temporary file that replaces `dst` on commit.
+    ///
+    /// Returns the path of the temporary file to write the executable to.
+    pub(crate) fn stage(&mut self, dst: &Path) -> Result<PathBuf, String> {
+        let temp = temp_builder().tempfile_in(parent(dst)).map_err(|e| {
+            format!(
+                "could not create a temporary file for {}: {e}",
+                dst.display()
+            )
+        })?;
+        let path = temp.path().to_path_buf();
+        self.staged.push((Staged::File(temp), dst.to_path_buf()));
+        Ok(path)
+    }
+
+    /// Create a temporary directory that replaces `dst` on commit.
+    ///
+    /// Returns the path of the empty directory to write the contents to.
+    pub(crate) fn stage_dir(&mut self, dst: &Path) -> Result<PathBuf, String> {
+        let temp = temp_builder()
+            .tempdir_in(parent(dst))
+            .and_then(|temp| {
+                std::fs::create_dir(temp.path().join(DIR_NAME))?;
+                Ok(temp)
+            })
+            .map_err(|e| {
+                format!(
+                    "could not create a temporary directory for {}: {e}",
+                    dst.display()
+                )
+            })?;
+        let path = temp.path().join(DIR_NAME);
+        self.staged.push((Staged::Dir(temp), dst.to_path_buf()));
+        Ok(path)
+    }
+
+    /// Whether a file or directory is staged to replace `dst`.
+    pub(crate) fn is_staged(&self, dst: &Path) -> bool {
+        self.staged.iter().any(|(_, staged)| staged == dst)
+    }
+
+    /// Move all staged files and directories into place.
+    ///
+    /// If any file cannot be moved, the files that were already moved are
+    /// restored to their previous versions. Returns the destinations.
+    pub(crate) fn commit(self) -> Result<Vec<PathBuf>, String> {
+        let mut committed = vec![];
+        for (staged, dst) in self.staged {
+            let backup = match &staged {
+                Staged::File(_) => backup(&dst),
+                Staged::Dir(_) => backup_dir(&dst),
+            };
+            let backup = match backup {
+                Ok(backup) => backup,
+                Err(e) => {
+                    rollback(committed);
+                    return Err(e);
+                }
+            };
+            let result = match staged {
+                Staged::File(temp) => temp.persist(&dst).map(|_| ()).map_err(|e| e.error),
+                Staged::Dir(temp) => std::fs::rename(temp.path().join(DIR_NAME), &dst),
+            };
+            if let Err(e) = result {
+                // A replaced directory was already moved aside.
+                if matches!(backup, Some(Backup::Dir(_))) {
+                    committed.push((dst.clone(), backup));
+                }
+                rollback(committed);
+                return Err(format!("could not move {} into place: {e}", dst.display()));
+            }
+            committed.push((dst, backup));
+        }
+        Ok(committed.into_iter().map(|(dst, _)| dst).collect())
+    }
+}
+
+#[cfg(test)]
+fn files_in(dir: &Path) -> Vec<String> {
+    let mut files = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+#[test]
+fn test_commit() {
+    let dir = tempfile::tempdir().unwrap();
+    let ffmpeg = dir.path().join("ffmpeg");
+    let ffprobe = dir.path().join("ffprobe");
+    std::fs::write(&ffmpeg, "old").unwrap();
+
+    let mut transaction = Transaction::new();
+    std::fs::write(transaction.stage(&ffmpeg).unwrap(), "new ffmpeg").unwrap();
+    std::fs::write(transaction.stage(&ffprobe).unwrap(), "new ffprobe").unwrap();
+    assert_eq!(std::fs::read_to_string(&ffmpeg).unwrap(), "old");
+    let placed = transaction.commit().unwrap();
+
+    assert_eq!(placed, vec![ffmpeg.clone(), ffprobe.clone()]);
+    assert_eq!(std::fs::read_to_string(&ffmpeg).unwrap(), "new ffmpeg");
+    assert_eq!(std::fs::read_to_string(&ffprobe).unwrap(), "new ffprobe");
+    assert_eq!(files_in(dir.path()), vec!["ffmpeg", "ffprobe"]);
+}
+
+#[test]
+fn test_drop_removes_staged_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let ffmpeg = dir.path().join("ffmpeg");
+    std::fs::write(&ffmpeg, "old").unwrap();
+
+    let mut transaction = Transaction::new();
+    std::fs::write(transaction.stage(&ffmpeg).unwrap(), "new").unwrap();
+    drop(transaction);
+
+    assert_eq!(std::fs::read_to_string(&ffmpeg).unwrap(), "old");
+    assert_eq!(files_in(dir.path()), vec!["ffmpeg"]);
+}
+
+#[test]
+fn test_commit_rolls_back() {
+    let dir = tempfile::tempdir().unwrap();
+    let ffmpeg = dir.path().join("ffmpeg");
+    let ffprobe = dir.path().join("ffprobe");
+    let ffplay = dir.path().join("ffplay");
+    std::fs::write(&ffmpeg, "old").unwrap();
+    // A directory cannot be replaced by a file, so the last rename fails.
+    std::fs::create_dir(&ffplay).unwrap();
+
+    let mut transaction = Transaction::new();
+    std::fs::write(transaction.stage(&ffmpeg).unwrap(), "new").unwrap();
+    std::fs::write(transaction.stage(&ffprobe).unwrap(), "new").unwrap();
+    std::fs::write(transaction.stage(&ffplay).unwrap(), "new").unwrap();
+    let result = transaction.commit();
+
+    assert!(result.unwrap_err().contains("could not move"));
+    assert_eq!(std::fs::read_to_string(&ffmpeg).unwrap(), "old");
+    assert!(!ffprobe.exists());
+    assert!(ffplay.is_dir());
+    assert_eq!(files_in(dir.path()), vec!["ffmpeg", "ffplay"]);
+}
+
+#[test]
+fn test_commit_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let ffmpeg = dir.path().join("ffmpeg");
+    let contents = dir.path().join("ffmpeg-7.1");
+    std::fs::create_dir(&contents).unwrap();
+    std::fs::write(contents.join("old.txt"), "old").unwrap();
+
+    let mut transaction = Transaction::new();
+    std::fs::write(transaction.stage(&ffmpeg).unwrap(), "new").unwrap();
+    let staged = transaction.stage_dir(&contents).unwrap();
+    std::fs::write(staged.join("new.txt"), "new").unwrap();
+    assert!(transaction.is_staged(&contents));
+    assert_eq!(files_in(&contents), vec!["old.txt"]);
+    transaction.commit().unwrap();
+
+    assert_eq!(files_in(dir.path()), vec!["ffmpeg", "ffmpeg-7.1"]);
+    assert_eq!(files_in(&contents), vec!["new.txt"]);
+}
+
+#[test]
+fn test_commit_dir_rolls_back() {
+    let dir = tempfile::tempdir().unwrap();
+    let contents = dir.path().join("ffmpeg-7.1");
+    let ffplay = dir.path().join("ffplay");
+    std::fs::create_dir(&contents).unwrap();
+    std::fs::write(contents.join("old.txt"), "old").unwrap();
+    std::fs::create_dir(&ffplay).unwrap();
+
+    let mut transaction = Transaction::new();
+    let staged = transaction.stage_dir(&contents).unwrap();
+    std::fs::write(staged.join("new.txt"), "new").unwrap();
+    std::fs::write(transaction.stage(&ffplay).unwrap(), "new").unwrap();
+    let result = transaction.commit();
+
+    assert!(result.unwrap_err().contains("could not move"));
+    assert_eq!(files_in(dir.path()), vec!["ffmpeg-7.1", "ffplay"]);
+    assert_eq!(files_in(&contents), vec!["old.txt"]);
+}
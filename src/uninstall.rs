@@ -0,0 +1,100 @@
+use crate::abort;
+use crate::receipt::read_receipts;
+use crate::receipt::InstalledFile;
+use crate::receipt::Receipt;
+use crate::sha::Sha256Hash;
+use crate::UninstallArgs;
+use std::path::Path;
+
+/// Whether a later install of another tool also claims `path`.
+///
+/// Installs drop the paths that they overwrite from the older receipts, but
+/// receipts written before that could still claim the same path.
+fn claimed_by_other(receipts: &[Receipt], current: &Receipt, path: &Path) -> bool {
+    receipts
+        .iter()
+        .filter(|receipt| !receipt.same_install(current))
+        .filter(|receipt| receipt.installed_at >= current.installed_at)
+        .any(|receipt| receipt.claims(path))
+}
+
+/// Verify that the file is still the one that jas placed.
+fn verify_unchanged(file: &InstalledFile) {
+    let actual = Sha256Hash::from_path(&file.path);
+    if file.sha != actual {
+        abort(&format!(
+            "Refusing to remove {} since it was modified after installation; expected SHA-256\n{}, but got\n{actual}",
+            file.path.display(),
+            file.sha
+        ));
+    }
+}
+
+/// Verify that the unpacked archive only contains the files that jas unpacked.
+fn verify_archive_unchanged(receipt: &Receipt, archive_dir: &Path) {
+    let files = crate::archive::files_in_dir(archive_dir)
+        .unwrap_or_else(|e| abort(&format!("Could not list {}: {e}", archive_dir.display())));
+    for path in files {
+        match receipt.archive_files.iter().find(|file| file.path == path) {
+            Some(file) => verify_unchanged(file),
+            None => abort(&format!(
+                "Refusing to remove {} since {} was not unpacked by jas",
+                archive_dir.display(),
+                path.display()
+            )),
+        }
+    }
+}
+
+fn keep(path: &Path) {
+    tracing::info!(
+        "Keeping {} since it belongs to another tool",
+        path.display()
+    );
+}
+
+/// Remove the executables and unpacked archive of an installed tool.
+///
+/// All files are verified before anything is removed, so that a modified
+/// file leaves the install intact.
+pub(crate) fn run(args: &UninstallArgs) {
+    let receipts = read_receipts();
+    let receipt = crate::receipt::find_receipt(&receipts, &args.name, &args.dir)
+        .unwrap_or_else(|e| abort(&e));
+    let mut executables = vec![];
+    for file in &receipt.executables {
+        if claimed_by_other(&receipts, receipt, &file.path) {
+            keep(&file.path);
+        } else if file.path.is_file() {
+            verify_unchanged(file);
+            executables.push(&file.path);
+        } else {
+            tracing::warn!("Could not find {}", file.path.display());
+        }
+    }
+    let archive_dir = receipt.archive_dir.as_deref().filter(|archive_dir| {
+        if claimed_by_other(&receipts, receipt, archive_dir) {
+            keep(archive_dir);
+            false
+        } else {
+            archive_dir.is_dir()
+        }
+    });
+    if let Some(archive_dir) = archive_dir {
+        verify_archive_unchanged(receipt, archive_dir);
+    }
+    for path in executables {
+        if let Err(e) = std::fs::remove_file(path) {
+            abort(&format!("Could not remove {}: {e}", path.display()));
+        }
+        tracing::info!("Removed {}", path.display());
+    }
+    if let Some(archive_dir) = archive_dir {
+        if let Err(e) = std::fs::remove_dir_all(archive_dir) {
+            abort(&format!("Could not remove {}: {e}", archive_dir.display()));
+        }
+        tracing::info!("Removed {}", archive_dir.display());
+    }
+    crate::receipt::remove_receipt(receipt);
+    tracing::info!("Uninstalled {}", receipt.name);
+}
@@ -0,0 +1,68 @@
+use crate::abort;
+use crate::guess::guess_asset;
+use crate::receipt::read_receipts;
+use crate::InstallArgs;
+use crate::UpdateArgs;
+
+/// Update an installed tool to the latest release.
+///
+/// The new asset is only installed if the new SHA-256 hash is passed via
+/// `--sha` or if `--accept-new-sha` is set, so that the user can review the
+/// hash first.
+pub(crate) fn run(args: &UpdateArgs) {
+    let receipts = read_receipts();
+    let receipt = crate::receipt::find_receipt(&receipts, &args.name, &args.dir)
+        .unwrap_or_else(|e| abort(&e));
+    let gh = match &receipt.gh {
+        Some(gh) => gh,
+        None => abort(&format!(
+            "Can only update tools that were installed with `--gh`, but {} was installed from {}",
+            receipt.name, receipt.url
+        )),
+    };
+    let (owner, repo, _) = crate::gh::parse_gh(gh);
+    let release = crate::gh::get_latest_release(owner, repo, &args.gh_token);
+    let latest = crate::gh::release_tag(&release);
+    if receipt.tag.as_deref() == Some(latest) {
+        tracing::info!("{} is already at the latest release {latest}", receipt.name);
+        return;
+    }
+    tracing::info!(
+        "Updating {} from {} to {latest}",
+        receipt.name,
+        receipt.tag.as_deref().unwrap_or("-")
+    );
+    let assets = crate::gh::release_assets(&release);
+    let names = crate::gh::asset_names(assets);
+    let asset = &assets[guess_asset(&names)];
+    let url = asset["browser_download_url"].as_str().unwrap();
+    let name = asset["name"].as_str().unwrap();
+    let pin = args.sha.as_deref().map(crate::sha::parse_pin);
+    let algorithms = pin.iter().map(|pin| pin.algorithm).collect::<Vec<_>>();
+    let download = crate::download::download_to_file(url, &algorithms, None);
+    let sha = download.sha256();
+    println!("{sha}");
+    match &pin {
+        Some(pin) => crate::sha::verify_pin(pin, download.digest(pin.algorithm)),
+        None => {
+            if !args.accept_new_sha {
+                tracing::warn!(
+                    "Not installing {name}; review the SHA-256 hash and pass it via `--sha` or pass `--accept-new-sha`"
+                );
+                return;
+            }
+        }
+    }
+    let install_args = InstallArgs {
+        gh: Some(format!("{owner}/{repo}@{latest}")),
+        gh_token: args.gh_token.clone(),
+        sha: Some(sha),
+        dir: receipt.dir.display().to_string(),
+        format: receipt.format,
+        archive_filename: receipt.archive_filename.clone(),
+        executable_filename: receipt.executable_filename.clone(),
+        keep_archive_contents: receipt.archive_dir.is_some(),
+        ..Default::default()
+    };
+    crate::install::install_download(&download, url, &install_args, name, &receipt.name);
+}
@@ -0,0 +1,68 @@
+use crate::abort;
+use crate::receipt::read_receipts;
+use crate::receipt::InstalledFile;
+use crate::sha::Sha256Hash;
+use crate::VerifyArgs;
+
+#[derive(Debug, PartialEq)]
+enum Status {
+    Ok,
+    Modified(String),
+    Missing,
+}
+
+fn check(file: &InstalledFile) -> Status {
+    if !file.path.is_file() {
+        return Status::Missing;
+    }
+    let actual = Sha256Hash::from_path(&file.path);
+    if file.sha == actual {
+        Status::Ok
+    } else {
+        Status::Modified(actual.to_string())
+    }
+}
+
+/// Verify that the installed executables still have the recorded SHA-256 hashes.
+pub(crate) fn run(args: &VerifyArgs) {
+    let receipts = read_receipts();
+    for name in &args.names {
+        if !receipts.iter().any(|receipt| &receipt.name == name) {
+            abort(&format!(
+                "No receipt found for {name}; see `jas list` for the installed tools"
+            ));
+        }
+    }
+    let receipts = receipts
+        .iter()
+        .filter(|receipt| args.names.is_empty() || args.names.contains(&receipt.name))
+        .collect::<Vec<_>>();
+    let mut checked = 0;
+    let mut failed = 0;
+    for receipt in receipts {
+        for file in &receipt.executables {
+            checked += 1;
+            let path = file.path.display();
+            match check(file) {
+                Status::Ok => println!("OK       {path}"),
+                Status::Modified(actual) => {
+                    failed += 1;
+                    println!(
+                        "MODIFIED {path}\n         expected {}\n         got      {actual}",
+                        file.sha
+                    );
+                }
+                Status::Missing => {
+                    failed += 1;
+                    println!("MISSING  {path}");
+                }
+            }
+        }
+    }
+    if failed > 0 {
+        abort(&format!(
+            "Verification failed for {failed} of {checked} files"
+        ));
+    }
+    tracing::info!("Verified {checked} files");
+}
@@ -0,0 +1,92 @@
+use assert_cmd::cargo;
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+fn bin() -> Command {
+    cargo::cargo_bin_cmd!("jas")
+}
+
+#[test]
+fn test_install_from_cache() {
+    let home = tempfile::tempdir().unwrap();
+    let cache = home.path().join("cache");
+    std::fs::create_dir_all(&cache).unwrap();
+    // The SHA-256 hash of "hello\n".
+    let sha = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+    std::fs::write(cache.join(sha), "hello\n").unwrap();
+    let bin_dir = home.path().join("bin");
+
+    // Nothing listens on the discard port, so the asset must come from the cache.
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--verbose")
+        .arg("--ansi=false")
+        .arg("install")
+        .arg("--url=http://127.0.0.1:9/hello")
+        .arg(format!("--sha={sha}"))
+        .arg(format!("--cache-dir={}", cache.display()))
+        .arg(format!("--dir={}", bin_dir.display()))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Using cached asset"));
+    assert_eq!(
+        std::fs::read_to_string(bin_dir.join("hello")).unwrap(),
+        "hello\n"
+    );
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("cache")
+        .arg("list")
+        .arg(format!("--cache-dir={}", cache.display()))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("{sha}  6")))
+        .stdout(predicate::str::contains("hello"));
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("cache")
+        .arg("clear")
+        .arg(format!("--cache-dir={}", cache.display()))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Removed 1 entries"));
+    assert!(!cache.join(sha).exists());
+}
+
+#[test]
+fn test_install_caches_only_pinned_assets() {
+    let home = tempfile::tempdir().unwrap();
+    let cache = home.path().join("cache");
+    let asset = home.path().join("hello");
+    std::fs::write(&asset, "hello\n").unwrap();
+    // The SHA-256 hash of "hello\n".
+    let sha = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+    let bin_dir = home.path().join("bin");
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("install")
+        .arg(format!("--path={}", asset.display()))
+        .arg(format!("--cache-dir={}", cache.display()))
+        .arg(format!("--dir={}", bin_dir.display()))
+        .assert()
+        .success();
+    assert!(!cache.join(sha).exists());
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("install")
+        .arg(format!("--path={}", asset.display()))
+        .arg(format!("--sha={sha}"))
+        .arg(format!("--cache-dir={}", cache.display()))
+        .arg(format!("--dir={}", bin_dir.display()))
+        .assert()
+        .success();
+    assert!(cache.join(sha).exists());
+}
@@ -1,6 +1,11 @@
+// Keep the tests as they were written before these lints were added to clippy.
+#![allow(clippy::collapsible_if, clippy::needless_borrows_for_generic_args)]
+
 use assert_cmd::cargo;
 use assert_cmd::Command;
 use predicates::prelude::*;
+use std::io::Read;
+use std::io::Write;
 use std::path::Path;
 
 fn bin() -> Command {
@@ -46,8 +51,10 @@ fn test_incorrect_sha() {
     // THIS IS THE MOST IMPORTANT TEST DO NOT REMOVE.
     //
     let sha = "0fb2401a46409bdf574f42f92df0418934166032ec2bcb0fc7919b7664fdcc01";
+    let home = tempfile::tempdir().unwrap();
     let mut cmd = bin();
-    cmd.arg("--verbose")
+    cmd.env("HOME", home.path())
+        .arg("--verbose")
         .arg("--ansi=false")
         .arg("install")
         .arg("--gh=crate-ci/typos@v1.31.1")
@@ -75,9 +82,11 @@ fn test_install_gh_guess_typos() {
         tracing::warn!("Skipping test on this platform");
         return;
     };
+    let home = tempfile::tempdir().unwrap();
     let mut cmd = bin();
     let expected_url = "https://github.com/crate-ci/typos/releases/download/v1.31.1/";
-    cmd.arg("--verbose")
+    cmd.env("HOME", home.path())
+        .arg("--verbose")
         .arg("--ansi=false")
         .arg("install")
         .arg("--gh=crate-ci/typos@v1.31.1")
@@ -113,9 +122,11 @@ fn test_install_gh_guess_just() {
         tracing::warn!("Skipping test on this platform");
         return;
     };
+    let home = tempfile::tempdir().unwrap();
     let mut cmd = bin();
     let expected_url = "https://github.com/casey/just/releases/download/1.40.0/";
-    cmd.arg("--verbose")
+    cmd.env("HOME", home.path())
+        .arg("--verbose")
         .arg("--ansi=false")
         .arg("install")
         .arg("--gh=casey/just@1.40.0")
@@ -151,8 +162,10 @@ fn test_install_gh_guess_typst() {
         tracing::warn!("Skipping test on this platform");
         return;
     };
+    let home = tempfile::tempdir().unwrap();
     let mut cmd = bin();
-    cmd.arg("--verbose")
+    cmd.env("HOME", home.path())
+        .arg("--verbose")
         .arg("--ansi=false")
         .arg("install")
         .arg("--gh=typst/typst@v0.13.0")
@@ -184,8 +197,10 @@ fn test_install_gh_guess_cargo_deny() {
         tracing::warn!("Skipping test on this platform");
         return;
     };
+    let home = tempfile::tempdir().unwrap();
     let mut cmd = bin();
-    cmd.arg("--verbose")
+    cmd.env("HOME", home.path())
+        .arg("--verbose")
         .arg("--ansi=false")
         .arg("install")
         .arg("--gh=EmbarkStudios/cargo-deny@0.18.2")
@@ -217,8 +232,10 @@ fn test_install_pandoc() {
         tracing::warn!("Skipping test on this platform");
         return;
     };
+    let home = tempfile::tempdir().unwrap();
     let mut cmd = bin();
-    cmd.arg("--verbose")
+    cmd.env("HOME", home.path())
+        .arg("--verbose")
         .arg("--ansi=false")
         .arg("install")
         .arg("--gh=jgm/pandoc@3.6.4")
@@ -253,8 +270,10 @@ fn test_install_ffmpeg_ffprobe() {
 
     // Chose this file because it's relatively small.
     let url = "https://www.johnvansickle.com/ffmpeg/old-releases/ffmpeg-6.0.1-armel-static.tar.xz";
+    let home = tempfile::tempdir().unwrap();
     let mut cmd = bin();
-    cmd.arg("--verbose")
+    cmd.env("HOME", home.path())
+        .arg("--verbose")
         .arg("--ansi=false")
         .arg("install")
         .arg("--url")
@@ -289,8 +308,10 @@ fn test_install_gh_no_guesses() {
     } else {
         "typos-v1.31.0-x86_64-unknown-linux-musl.tar.gz"
     };
+    let home = tempfile::tempdir().unwrap();
     let mut cmd = bin();
-    cmd.arg("--verbose")
+    cmd.env("HOME", home.path())
+        .arg("--verbose")
         .arg("--ansi=false")
         .arg("install")
         .arg("--gh=crate-ci/typos@v1.31.0")
@@ -306,7 +327,8 @@ fn test_install_gh_no_guesses() {
         ));
 
     let mut cmd = bin();
-    cmd.arg("--verbose")
+    cmd.env("HOME", home.path())
+        .arg("--verbose")
         .arg("--ansi=false")
         .arg("install")
         .arg("--gh=crate-ci/typos@v1.31.0")
@@ -340,8 +362,10 @@ fn test_install_url() {
     } else {
         return;
     };
+    let home = tempfile::tempdir().unwrap();
     let mut cmd = bin();
-    cmd.arg("--verbose")
+    cmd.env("HOME", home.path())
+        .arg("--verbose")
         .arg("--ansi=false")
         .arg("install")
         .arg("--url")
@@ -365,3 +389,419 @@ fn test_install_url() {
         .success()
         .stdout(predicate::str::contains("0.5.0"));
 }
+
+#[test]
+fn test_install_resolved_tag_requires_sha() {
+    let home = tempfile::tempdir().unwrap();
+    for gh in [
+        "crate-ci/typos",
+        "crate-ci/typos@latest",
+        "crate-ci/typos@^1.31",
+    ] {
+        let mut cmd = bin();
+        cmd.env("HOME", home.path())
+            .arg("--verbose")
+            .arg("--ansi=false")
+            .arg("install")
+            .arg(format!("--gh={gh}"))
+            .arg("--dir=tests")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("requires `--sha`"));
+    }
+}
+
+#[test]
+fn test_install_sha_from_release_requires_gh() {
+    let home = tempfile::tempdir().unwrap();
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--verbose")
+        .arg("--ansi=false")
+        .arg("install")
+        .arg("--url=https://github.com/transformrs/trv/releases/download/v0.5.0/trv-x86_64-unknown-linux-gnu")
+        .arg("--sha-from-release")
+        .arg("--dir=tests")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("`--sha-from-release` requires `--gh`"));
+}
+
+#[test]
+fn test_install_cosign_identity_requires_issuer() {
+    let home = tempfile::tempdir().unwrap();
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--verbose")
+        .arg("--ansi=false")
+        .arg("install")
+        .arg("--url=https://github.com/transformrs/trv/releases/download/v0.5.0/trv-x86_64-unknown-linux-gnu")
+        .arg("--cosign-identity=^https://github.com/transformrs/trv/")
+        .arg("--dir=tests")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--cosign-issuer"));
+}
+
+/// Return a tar archive with the given executable files.
+fn tar_bytes(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(vec![]);
+    for (name, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder.append_data(&mut header, name, *content).unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+/// Write a `.tar.gz` with the given files and return its SHA-256 hash.
+fn write_tar_gz(path: &Path, files: &[(&str, &[u8])]) -> String {
+    let file = std::fs::File::create(path).unwrap();
+    let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    encoder.write_all(&tar_bytes(files)).unwrap();
+    encoder.finish().unwrap();
+    let mut cmd = bin();
+    let output = cmd
+        .arg("sha")
+        .arg(format!("--path={}", path.display()))
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+/// Return the command to install the local `asset` into `dir`.
+fn install_path(home: &Path, asset: &Path, dir: &Path) -> Command {
+    let mut cmd = bin();
+    cmd.env("HOME", home)
+        .arg("--ansi=false")
+        .arg("install")
+        .arg(format!("--path={}", asset.display()))
+        .arg(format!("--dir={}", dir.display()));
+    cmd
+}
+
+#[test]
+fn test_install_path() {
+    let home = tempfile::tempdir().unwrap();
+    let archive = home
+        .path()
+        .join("typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz");
+    let sha = write_tar_gz(&archive, &[("typos", b"#!/bin/sh\necho typos\n")]);
+    let bin_dir = home.path().join("bin");
+
+    install_path(home.path(), &archive, &bin_dir)
+        .arg(format!("--sha={sha}"))
+        .assert()
+        .success();
+    let typos = bin_dir.join(add_exe_if_needed("typos"));
+    assert_eq!(
+        std::fs::read_to_string(typos).unwrap(),
+        "#!/bin/sh\necho typos\n"
+    );
+
+    let incorrect = "0fb2401a46409bdf574f42f92df0418934166032ec2bcb0fc7919b7664fdcc01";
+    install_path(home.path(), &archive, &bin_dir)
+        .arg(format!("--sha={incorrect}"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SHA-256 mismatch: expected"));
+}
+
+#[test]
+fn test_install_path_signature() {
+    let home = tempfile::tempdir().unwrap();
+    let asset = home.path().join("hello.txt");
+    std::fs::copy("tests/data/pgp/hello.txt", &asset).unwrap();
+    let bin_dir = home.path().join("bin");
+
+    install_path(home.path(), &asset, &bin_dir)
+        .arg("--pgp-key=tests/data/pgp/ed25519.pub.asc")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Could not find a PGP signature"));
+
+    // The signature is read from next to the local asset.
+    let signature = home.path().join("hello.txt.sig");
+    std::fs::copy("tests/data/pgp/hello.txt.ed25519.sig", signature).unwrap();
+    install_path(home.path(), &asset, &bin_dir)
+        .arg("--pgp-key=tests/data/pgp/ed25519.pub.asc")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_install_path_selective_extraction() {
+    let home = tempfile::tempdir().unwrap();
+    let archive = home
+        .path()
+        .join("tool-v1.0.0-x86_64-unknown-linux-musl.tar.gz");
+    write_tar_gz(
+        &archive,
+        &[
+            ("tool-v1.0.0/README.md", b"readme"),
+            ("tool-v1.0.0/doc/tool.1", b"manual"),
+            ("tool-v1.0.0/tool", b"binary"),
+        ],
+    );
+    let bin_dir = home.path().join("bin");
+    let archive_dir = bin_dir.join("tool-v1.0.0-x86_64-unknown-linux-musl.tar");
+
+    install_path(home.path(), &archive, &bin_dir)
+        .assert()
+        .success();
+    let tool = bin_dir.join(add_exe_if_needed("tool"));
+    assert_eq!(std::fs::read_to_string(&tool).unwrap(), "binary");
+    assert!(!archive_dir.exists());
+
+    install_path(home.path(), &archive, &bin_dir)
+        .arg("--keep-archive-contents")
+        .assert()
+        .success();
+    assert_eq!(std::fs::read_to_string(&tool).unwrap(), "binary");
+    let manual = archive_dir.join("tool-v1.0.0").join("doc").join("tool.1");
+    assert_eq!(std::fs::read_to_string(manual).unwrap(), "manual");
+}
+
+#[test]
+fn test_install_path_archive_contents_collision() {
+    let home = tempfile::tempdir().unwrap();
+    let archive = home.path().join("tool.tar.gz");
+    write_tar_gz(
+        &archive,
+        &[("tool/tool", b"new"), ("tool/README.md", b"readme")],
+    );
+    let bin_dir = home.path().join("bin");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    let tool = bin_dir.join("tool.tar");
+    std::fs::write(&tool, "old").unwrap();
+
+    // The executable would be replaced by the directory with the archive contents.
+    install_path(home.path(), &archive, &bin_dir)
+        .arg("--archive-filename=tool")
+        .arg("--executable-filename=tool.tar")
+        .arg("--keep-archive-contents")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "is both an executable and the directory for the archive contents",
+        ));
+    assert_eq!(std::fs::read_to_string(&tool).unwrap(), "old");
+    let files = std::fs::read_dir(&bin_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(files, vec!["tool.tar"]);
+}
+
+#[test]
+fn test_install_path_rolls_back() {
+    let home = tempfile::tempdir().unwrap();
+    let archive = home.path().join("ffmpeg-7.1-linux64.tar.gz");
+    write_tar_gz(
+        &archive,
+        &[
+            ("ffmpeg-7.1/ffmpeg", b"new"),
+            ("ffmpeg-7.1/ffprobe", b"new"),
+        ],
+    );
+    let bin_dir = home.path().join("bin");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    let ffmpeg = bin_dir.join(add_exe_if_needed("ffmpeg"));
+    std::fs::write(&ffmpeg, "old").unwrap();
+    // A directory cannot be replaced by an executable, so placing ffprobe fails.
+    std::fs::create_dir(bin_dir.join(add_exe_if_needed("ffprobe"))).unwrap();
+
+    install_path(home.path(), &archive, &bin_dir)
+        .arg("--archive-filename=ffmpeg")
+        .arg("--archive-filename=ffprobe")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Could not install"));
+    assert_eq!(std::fs::read_to_string(&ffmpeg).unwrap(), "old");
+    let leftovers = std::fs::read_dir(&bin_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_str().unwrap().to_string())
+        .filter(|name| name.starts_with(".jas-"))
+        .collect::<Vec<_>>();
+    assert!(leftovers.is_empty(), "leftovers: {leftovers:?}");
+}
+
+fn read_all(mut reader: impl Read) -> Vec<u8> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn test_install_path_compressed_formats() {
+    let home = tempfile::tempdir().unwrap();
+    let bin_dir = home.path().join("bin");
+    let content = b"#!/bin/sh\necho tool\n";
+
+    let tar = tar_bytes(&[("tool", content)]);
+
+    let mut bz2 = bzip2::write::BzEncoder::new(vec![], bzip2::Compression::default());
+    bz2.write_all(&tar).unwrap();
+    let assets = [
+        (
+            "tool",
+            "tool-v1.0.0-x86_64-unknown-linux-musl.tar.bz2",
+            bz2.finish().unwrap(),
+        ),
+        (
+            "tool",
+            "tool-v1.0.0-x86_64-unknown-linux-musl.tar.zst",
+            zstd::encode_all(&tar[..], 0).unwrap(),
+        ),
+        (
+            "gz",
+            "gz-x86_64-unknown-linux-gnu.gz",
+            read_all(flate2::read::GzEncoder::new(
+                &content[..],
+                flate2::Compression::default(),
+            )),
+        ),
+        (
+            "xz",
+            "xz-x86_64-unknown-linux-gnu.xz",
+            read_all(xz2::read::XzEncoder::new(&content[..], 6)),
+        ),
+    ];
+    for (executable, name, data) in assets {
+        let asset = home.path().join(name);
+        std::fs::write(&asset, data).unwrap();
+
+        install_path(home.path(), &asset, &bin_dir)
+            .assert()
+            .success();
+        let executable = bin_dir.join(add_exe_if_needed(executable));
+        assert_eq!(std::fs::read(&executable).unwrap(), content, "{name}");
+    }
+}
+
+#[test]
+fn test_install_path_executable_filename() {
+    let home = tempfile::tempdir().unwrap();
+    let bin_dir = home.path().join("bin");
+    let asset = home
+        .path()
+        .join("rust-analyzer-x86_64-unknown-linux-gnu.gz");
+    let content = b"#!/bin/sh\necho rust-analyzer\n";
+    let data = read_all(flate2::read::GzEncoder::new(
+        &content[..],
+        flate2::Compression::default(),
+    ));
+    std::fs::write(&asset, data).unwrap();
+
+    install_path(home.path(), &asset, &bin_dir)
+        .assert()
+        .success();
+    let executable = bin_dir.join(add_exe_if_needed("rust-analyzer"));
+    assert_eq!(std::fs::read(&executable).unwrap(), content);
+
+    install_path(home.path(), &asset, &bin_dir)
+        .arg("--executable-filename=ra")
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read(bin_dir.join(add_exe_if_needed("ra"))).unwrap(),
+        content
+    );
+}
+
+#[test]
+fn test_install_path_format() {
+    let home = tempfile::tempdir().unwrap();
+    let bin_dir = home.path().join("bin");
+    // A name without an extension, such as from a redirecting download URL.
+    let asset = home.path().join("tool");
+    write_tar_gz(&asset, &[("tool", b"binary")]);
+
+    install_path(home.path(), &asset, &bin_dir)
+        .assert()
+        .success();
+    let executable = bin_dir.join(add_exe_if_needed("tool"));
+    assert_eq!(std::fs::read(&executable).unwrap(), b"binary");
+
+    install_path(home.path(), &asset, &bin_dir)
+        .arg("--format=binary")
+        .assert()
+        .success();
+    // The override is recorded so that `jas update` does not detect it again.
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("list")
+        .arg("--json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""format": "binary""#));
+    assert_eq!(
+        std::fs::read(&executable).unwrap(),
+        std::fs::read(&asset).unwrap()
+    );
+}
+
+fn write_deb(path: &Path, files: &[(&str, &[u8])]) {
+    let data = read_all(xz2::read::XzEncoder::new(&tar_bytes(files)[..], 6));
+    let members: [(&str, &[u8]); 3] = [
+        ("debian-binary", b"2.0\n"),
+        // Maintainer scripts are never run, so the control archive is ignored.
+        ("control.tar.xz", b""),
+        ("data.tar.xz", &data),
+    ];
+    let mut deb = ar::Builder::new(std::fs::File::create(path).unwrap());
+    for (name, content) in members {
+        let header = ar::Header::new(name.as_bytes().to_vec(), content.len() as u64);
+        deb.append(&header, content).unwrap();
+    }
+}
+
+#[test]
+fn test_install_path_deb() {
+    let home = tempfile::tempdir().unwrap();
+    let bin_dir = home.path().join("bin");
+    let deb = home.path().join("tool_1.0.0-1_amd64.deb");
+    write_deb(
+        &deb,
+        &[
+            ("./usr/bin/tool", b"tool"),
+            ("./usr/bin/tool-server", b"server"),
+            ("./usr/share/doc/tool/copyright", b"copyright"),
+        ],
+    );
+
+    install_path(home.path(), &deb, &bin_dir).assert().success();
+    let tool = bin_dir.join(add_exe_if_needed("tool"));
+    assert_eq!(std::fs::read(&tool).unwrap(), b"tool");
+
+    install_path(home.path(), &deb, &bin_dir)
+        .arg("--archive-filename=tool-server")
+        .assert()
+        .success();
+    let server = bin_dir.join(add_exe_if_needed("tool-server"));
+    assert_eq!(std::fs::read(&server).unwrap(), b"server");
+    assert!(!bin_dir.join("usr").exists());
+}
+
+#[test]
+fn test_install_path_rpm() {
+    let home = tempfile::tempdir().unwrap();
+    let bin_dir = home.path().join("bin");
+    // Generated by `test_rpm_fixture` in `src/archive.rs`, with a gzip payload
+    // that contains `usr/bin/tool`, `usr/bin/tool-server`, and a README.
+    let rpm = Path::new("tests/data/rpm/tool-1.0.0-1.x86_64.rpm");
+
+    install_path(home.path(), rpm, &bin_dir)
+        .arg("--archive-filename=tool")
+        .arg("--archive-filename=tool-server")
+        .assert()
+        .success();
+    let tool = bin_dir.join(add_exe_if_needed("tool"));
+    assert_eq!(std::fs::read(&tool).unwrap(), b"tool");
+    let server = bin_dir.join(add_exe_if_needed("tool-server"));
+    assert_eq!(std::fs::read(&server).unwrap(), b"server");
+    assert!(!bin_dir.join("usr").exists());
+}
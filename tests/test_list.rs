@@ -0,0 +1,74 @@
+use assert_cmd::cargo;
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+fn bin() -> Command {
+    cargo::cargo_bin_cmd!("jas")
+}
+
+#[test]
+fn test_list_empty() {
+    let home = tempfile::tempdir().unwrap();
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("list")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("No tools installed"));
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("list")
+        .arg("--json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[]"));
+}
+
+#[test]
+fn test_list_receipts() {
+    let home = tempfile::tempdir().unwrap();
+    let dir = home.path().join(".jas");
+    std::fs::create_dir_all(&dir).unwrap();
+    let receipts = r#"{
+      "receipts": [
+        {
+          "name": "typos",
+          "gh": "crate-ci/typos",
+          "tag": "v1.31.1",
+          "url": "https://github.com/crate-ci/typos/releases/download/v1.31.1/typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz",
+          "asset_name": "typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz",
+          "sha": "f683c2abeaff70379df7176110100e18150ecd17a4b9785c32908aca11929993",
+          "dir": "/home/user/.jas/bin",
+          "archive_filename": null,
+          "executable_filename": null,
+          "executables": [],
+          "archive_dir": null,
+          "installed_at": "2025-04-08T15:05:06Z",
+          "jas_version": "0.3.2"
+        }
+      ]
+    }"#;
+    std::fs::write(dir.join("receipts.json"), receipts).unwrap();
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("typos  crate-ci/typos  v1.31.1"));
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("list")
+        .arg("--json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#""asset_name": "typos-v1.31.1-x86_64-unknown-linux-musl.tar.gz""#,
+        ));
+}
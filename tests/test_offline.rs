@@ -0,0 +1,230 @@
+use assert_cmd::cargo;
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+fn bin() -> Command {
+    cargo::cargo_bin_cmd!("jas")
+}
+
+// The SHA-256 hash of "hello\n".
+static SHA: &str = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+
+// The SHA-256 hash of "hello riscv64\n".
+static OTHER_SHA: &str = "f629f335ad82fad2a4f43c325ef2eaa3a167b20ee25f9b7dbe5d38e9707d8968";
+
+#[test]
+fn test_offline_url_not_cached() {
+    let home = tempfile::tempdir().unwrap();
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("install")
+        .arg("--offline")
+        .arg("--url=https://example.com/hello")
+        .arg(format!("--sha={SHA}"))
+        .arg(format!("--dir={}", home.path().join("bin").display()))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "`--offline` forbids network access",
+        ));
+}
+
+#[test]
+fn test_offline_gh_not_resolved() {
+    let home = tempfile::tempdir().unwrap();
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .current_dir(home.path())
+        .arg("--ansi=false")
+        .arg("install")
+        .arg("--offline")
+        .arg("--gh=crate-ci/typos@v1.31.1")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Cannot resolve crate-ci/typos@v1.31.1 with `--offline`",
+        ));
+}
+
+#[test]
+fn test_offline_gh_from_receipt() {
+    let home = tempfile::tempdir().unwrap();
+    let jas_dir = home.path().join(".jas");
+    std::fs::create_dir_all(&jas_dir).unwrap();
+    let bin_dir = home.path().join("bin");
+    let receipts = format!(
+        r#"{{
+      "receipts": [
+        {{
+          "name": "hello",
+          "gh": "example/hello",
+          "tag": "v1.0.0",
+          "url": "https://example.com/hello",
+          "asset_name": "hello",
+          "sha": "{SHA}",
+          "dir": "/home/user/.jas/bin",
+          "archive_filename": null,
+          "executable_filename": null,
+          "executables": [],
+          "archive_dir": null,
+          "installed_at": "2025-04-08T15:05:06Z",
+          "jas_version": "0.3.2"
+        }}
+      ]
+    }}"#
+    );
+    std::fs::write(jas_dir.join("receipts.json"), receipts).unwrap();
+    let asset = home.path().join("hello-asset");
+    std::fs::write(&asset, "hello\n").unwrap();
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .current_dir(home.path())
+        .arg("--verbose")
+        .arg("--ansi=false")
+        .arg("install")
+        .arg("--offline")
+        .arg("--gh=example/hello@^1")
+        .arg(format!("--from-file={}", asset.display()))
+        .arg(format!("--dir={}", bin_dir.display()))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Using hello from the receipt"));
+    assert_eq!(
+        std::fs::read_to_string(bin_dir.join("hello")).unwrap(),
+        "hello\n"
+    );
+
+    // The receipt pins the hash, so a different file is rejected.
+    std::fs::write(&asset, "tampered\n").unwrap();
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .current_dir(home.path())
+        .arg("--ansi=false")
+        .arg("install")
+        .arg("--offline")
+        .arg("--gh=example/hello@v1.0.0")
+        .arg(format!("--from-file={}", asset.display()))
+        .arg(format!("--dir={}", bin_dir.display()))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SHA-256 mismatch"));
+}
+
+#[test]
+fn test_offline_sha_from_release() {
+    let home = tempfile::tempdir().unwrap();
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .current_dir(home.path())
+        .arg("--ansi=false")
+        .arg("install")
+        .arg("--offline")
+        .arg("--sha-from-release")
+        .arg("--gh=crate-ci/typos@v1.31.1")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "`--sha-from-release` cannot be used with `--offline`",
+        ));
+}
+
+#[test]
+fn test_offline_gh_from_lockfile() {
+    let home = tempfile::tempdir().unwrap();
+    let lockfile = format!(
+        r#"[[tool]]
+gh = "example/hello@v1.0.0"
+
+[[tool.asset]]
+platform = "any"
+name = "hello"
+url = "https://example.com/hello"
+sha = "{SHA}"
+
+[[tool.asset]]
+platform = "linux-riscv64"
+name = "hello-riscv64"
+url = "https://example.com/hello-riscv64"
+sha = "{OTHER_SHA}"
+"#
+    );
+    std::fs::write(home.path().join("jas.lock"), lockfile).unwrap();
+    let asset = home.path().join("hello-asset");
+    std::fs::write(&asset, "hello\n").unwrap();
+    let bin_dir = home.path().join("bin");
+
+    // The lockfile in the working directory is only read when asked for.
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .current_dir(home.path())
+        .arg("--ansi=false")
+        .arg("install")
+        .arg("--offline")
+        .arg("--gh=example/hello@v1.0.0")
+        .arg(format!("--from-file={}", asset.display()))
+        .arg(format!("--dir={}", bin_dir.display()))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Cannot resolve example/hello@v1.0.0 with `--offline`",
+        ));
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .current_dir(home.path())
+        .arg("--ansi=false")
+        .arg("install")
+        .arg("--offline")
+        .arg("--gh=example/hello@v1.0.0")
+        .arg("--lockfile=jas.lock")
+        .arg("--asset-name=hello-linux")
+        .arg(format!("--from-file={}", asset.display()))
+        .arg(format!("--dir={}", bin_dir.display()))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "`--asset-name` hello-linux conflicts with the locked asset hello in jas.lock",
+        ));
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .current_dir(home.path())
+        .arg("--ansi=false")
+        .arg("install")
+        .arg("--offline")
+        .arg("--gh=example/hello@v1.0.0")
+        .arg("--lockfile=jas.lock")
+        .arg("--sha=0fb2401a46409bdf574f42f92df0418934166032ec2bcb0fc7919b7664fdcc01")
+        .arg(format!("--from-file={}", asset.display()))
+        .arg(format!("--dir={}", bin_dir.display()))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "conflicts with the locked hashes in jas.lock",
+        ));
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .current_dir(home.path())
+        .arg("--verbose")
+        .arg("--ansi=false")
+        .arg("install")
+        .arg("--offline")
+        .arg("--gh=example/hello@v1.0.0")
+        .arg("--lockfile=jas.lock")
+        // A hash that was taken on another platform is overridden by the lock.
+        .arg(format!("--sha={OTHER_SHA}"))
+        .arg(format!("--from-file={}", asset.display()))
+        .arg(format!("--dir={}", bin_dir.display()))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Using hello for any from jas.lock",
+        ));
+    assert_eq!(
+        std::fs::read_to_string(bin_dir.join("hello")).unwrap(),
+        "hello\n"
+    );
+}
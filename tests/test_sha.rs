@@ -36,3 +36,19 @@ fn test_sha_url() {
         .stderr(predicate::str::contains(url))
         .stdout(predicate::str::contains(expected));
 }
+
+#[test]
+#[cfg(not(windows))]
+fn test_sha_algo() {
+    // Obtained via `sha512sum LICENSE`.
+    let expected = "sha512:29c39cf755a7770d65729e8ac1ec3deec7e76f7d3a4a01319015995241c273767432309d39ee964954910cb5f4f3eee6fdaec6923ef3296c4a2d1edd03fb0697";
+    let mut cmd = bin();
+    cmd.arg("--verbose")
+        .arg("--ansi=false")
+        .arg("sha")
+        .arg("--path=LICENSE")
+        .arg("--algo=sha512")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(expected));
+}
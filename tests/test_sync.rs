@@ -0,0 +1,41 @@
+use assert_cmd::cargo;
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+fn bin() -> Command {
+    cargo::cargo_bin_cmd!("jas")
+}
+
+#[test]
+fn test_sync_missing_manifest() {
+    let mut cmd = bin();
+    cmd.arg("--verbose")
+        .arg("--ansi=false")
+        .arg("sync")
+        .arg("--manifest=tests/this_manifest_does_not_exist.toml")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Could not read manifest"));
+}
+
+#[test]
+fn test_sync_incorrect_sha() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = dir.path().join("jas.toml");
+    let text = r#"
+        [[tool]]
+        gh = "crate-ci/typos@v1.31.1"
+        dir = "tests"
+        sha = "0fb2401a46409bdf574f42f92df0418934166032ec2bcb0fc7919b7664fdcc01"
+    "#;
+    std::fs::write(&manifest, text).unwrap();
+    let mut cmd = bin();
+    cmd.env("HOME", dir.path())
+        .arg("--verbose")
+        .arg("--ansi=false")
+        .arg("sync")
+        .arg(format!("--manifest={}", manifest.display()))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("SHA-256 mismatch: expected"));
+}
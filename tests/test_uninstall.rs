@@ -0,0 +1,197 @@
+use assert_cmd::cargo;
+use assert_cmd::Command;
+use predicates::prelude::*;
+use sha2::Digest;
+use std::path::Path;
+
+fn bin() -> Command {
+    cargo::cargo_bin_cmd!("jas")
+}
+
+fn sha(path: &Path) -> String {
+    hex::encode(sha2::Sha256::digest(std::fs::read(path).unwrap()))
+}
+
+/// Write a receipt for a tool named `tool` that placed the given files.
+fn write_receipts(home: &Path, executable: &Path, archive_dir: &Path) {
+    let bin_dir = executable.parent().unwrap();
+    let archive_files = std::fs::read_dir(archive_dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            serde_json::json!({ "path": path, "sha": sha(&path) })
+        })
+        .collect::<Vec<_>>();
+    let dir = home.join(".jas");
+    std::fs::create_dir_all(&dir).unwrap();
+    let receipts = serde_json::json!({
+        "receipts": [
+            {
+                "name": "tool",
+                "gh": "owner/tool",
+                "tag": "v1.0.0",
+                "url": "https://github.com/owner/tool/releases/download/v1.0.0/tool.tar.gz",
+                "asset_name": "tool.tar.gz",
+                "sha": "0fb2401a46409bdf574f42f92df0418934166032ec2bcb0fc7919b7664fdcc01",
+                "dir": bin_dir,
+                "archive_filename": null,
+                "executable_filename": null,
+                "executables": [{ "path": executable, "sha": sha(executable) }],
+                "archive_dir": archive_dir,
+                "archive_files": archive_files,
+                "installed_at": "2025-04-08T15:05:06Z",
+                "jas_version": "0.3.2"
+            }
+        ]
+    });
+    std::fs::write(dir.join("receipts.json"), receipts.to_string()).unwrap();
+}
+
+#[test]
+fn test_uninstall() {
+    let home = tempfile::tempdir().unwrap();
+    let bin_dir = home.path().join("bin");
+    let archive_dir = bin_dir.join("tool-v1.0.0");
+    std::fs::create_dir_all(&archive_dir).unwrap();
+    std::fs::write(archive_dir.join("tool"), "binary").unwrap();
+    let executable = bin_dir.join("tool");
+    std::fs::write(&executable, "binary").unwrap();
+    let other = bin_dir.join("other");
+    std::fs::write(&other, "not installed by jas").unwrap();
+    write_receipts(home.path(), &executable, &archive_dir);
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("uninstall")
+        .arg("tool")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Uninstalled tool"));
+    assert!(!executable.exists());
+    assert!(!archive_dir.exists());
+    assert!(other.exists());
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("uninstall")
+        .arg("tool")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No receipt found for tool"));
+}
+
+#[test]
+fn test_uninstall_modified() {
+    let home = tempfile::tempdir().unwrap();
+    let archive_dir = home.path().join("tool-archive");
+    std::fs::create_dir_all(&archive_dir).unwrap();
+    let executable = home.path().join("tool");
+    std::fs::write(&executable, "binary").unwrap();
+    write_receipts(home.path(), &executable, &archive_dir);
+    std::fs::write(&executable, "replaced by someone else").unwrap();
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("uninstall")
+        .arg("tool")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Refusing to remove"));
+    assert!(executable.exists());
+    assert!(archive_dir.exists());
+}
+
+#[test]
+fn test_uninstall_unpacked_file_added() {
+    let home = tempfile::tempdir().unwrap();
+    let archive_dir = home.path().join("tool-archive");
+    std::fs::create_dir_all(&archive_dir).unwrap();
+    std::fs::write(archive_dir.join("README.md"), "readme").unwrap();
+    let executable = home.path().join("tool");
+    std::fs::write(&executable, "binary").unwrap();
+    write_receipts(home.path(), &executable, &archive_dir);
+    std::fs::write(archive_dir.join("notes.txt"), "written by the user").unwrap();
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("uninstall")
+        .arg("tool")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("was not unpacked by jas"));
+    assert!(executable.exists());
+    assert!(archive_dir.join("notes.txt").exists());
+}
+
+fn install_path(home: &Path, asset: &Path, args: &[&str]) {
+    let mut cmd = bin();
+    cmd.env("HOME", home)
+        .arg("--ansi=false")
+        .arg("install")
+        .arg(format!("--path={}", asset.display()))
+        .arg(format!("--dir={}", home.join("bin").display()))
+        .args(args)
+        .assert()
+        .success();
+}
+
+fn uninstall(home: &Path, name: &str) {
+    let mut cmd = bin();
+    cmd.env("HOME", home)
+        .arg("--ansi=false")
+        .arg("uninstall")
+        .arg(name)
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_uninstall_overwritten_by_other_tool() {
+    let home = tempfile::tempdir().unwrap();
+    let archive = home.path().join("ffmpeg-7.1-linux64.tar");
+    let mut builder = tar::Builder::new(std::fs::File::create(&archive).unwrap());
+    for name in ["ffmpeg", "ffprobe"] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(name.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, name, name.as_bytes())
+            .unwrap();
+    }
+    builder.finish().unwrap();
+    let other = home.path().join("ffprobe");
+    std::fs::write(&other, "another ffprobe").unwrap();
+    let ffmpeg = home.path().join("bin").join("ffmpeg");
+    let ffprobe = home.path().join("bin").join("ffprobe");
+
+    for first in ["ffmpeg", "ffprobe"] {
+        install_path(
+            home.path(),
+            &archive,
+            &["--archive-filename=ffmpeg", "--archive-filename=ffprobe"],
+        );
+        install_path(home.path(), &other, &[]);
+
+        // Each tool only removes the files that it placed last.
+        uninstall(home.path(), first);
+        if first == "ffmpeg" {
+            assert!(!ffmpeg.exists());
+            assert_eq!(
+                std::fs::read_to_string(&ffprobe).unwrap(),
+                "another ffprobe"
+            );
+            uninstall(home.path(), "ffprobe");
+        } else {
+            assert!(!ffprobe.exists());
+            assert!(ffmpeg.exists());
+            uninstall(home.path(), "ffmpeg");
+        }
+        assert!(!ffmpeg.exists());
+        assert!(!ffprobe.exists());
+    }
+}
@@ -0,0 +1,33 @@
+use assert_cmd::cargo;
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+fn bin() -> Command {
+    cargo::cargo_bin_cmd!("jas")
+}
+
+#[test]
+fn test_outdated_nothing_installed() {
+    let home = tempfile::tempdir().unwrap();
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("outdated")
+        .arg("--manifest=tests/this_manifest_does_not_exist.toml")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("No tools from GitHub releases"));
+}
+
+#[test]
+fn test_update_not_installed() {
+    let home = tempfile::tempdir().unwrap();
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("update")
+        .arg("typos")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No receipt found for typos"));
+}
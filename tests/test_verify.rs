@@ -0,0 +1,99 @@
+use assert_cmd::cargo;
+use assert_cmd::Command;
+use predicates::prelude::*;
+use sha2::Digest;
+use std::path::Path;
+
+fn bin() -> Command {
+    cargo::cargo_bin_cmd!("jas")
+}
+
+fn sha(path: &Path) -> String {
+    hex::encode(sha2::Sha256::digest(std::fs::read(path).unwrap()))
+}
+
+/// Write a receipt for a tool named `ffmpeg` that placed the given files.
+fn write_receipts(home: &Path, executables: &[&Path]) {
+    let dir = home.join(".jas");
+    std::fs::create_dir_all(&dir).unwrap();
+    let executables = executables
+        .iter()
+        .map(|path| serde_json::json!({ "path": path, "sha": sha(path) }))
+        .collect::<Vec<_>>();
+    let receipts = serde_json::json!({
+        "receipts": [
+            {
+                "name": "ffmpeg",
+                "gh": null,
+                "tag": null,
+                "url": "https://www.johnvansickle.com/ffmpeg/old-releases/ffmpeg-6.0.1-armel-static.tar.xz",
+                "asset_name": "ffmpeg-6.0.1-armel-static.tar.xz",
+                "sha": "1c2dd0795990796c29d0da8b0842e0bb13daf35eee062087a78cd70131301d58",
+                "dir": home,
+                "archive_filename": ["ffmpeg", "ffprobe"],
+                "executable_filename": null,
+                "executables": executables,
+                "archive_dir": null,
+                "installed_at": "2025-04-08T15:05:06Z",
+                "jas_version": "0.3.2"
+            }
+        ]
+    });
+    std::fs::write(dir.join("receipts.json"), receipts.to_string()).unwrap();
+}
+
+#[test]
+fn test_verify() {
+    let home = tempfile::tempdir().unwrap();
+    let ffmpeg = home.path().join("ffmpeg");
+    std::fs::write(&ffmpeg, "ffmpeg").unwrap();
+    let ffprobe = home.path().join("ffprobe");
+    std::fs::write(&ffprobe, "ffprobe").unwrap();
+    write_receipts(home.path(), &[&ffmpeg, &ffprobe]);
+
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("verify")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "OK       {}",
+            ffprobe.display()
+        )))
+        .stderr(predicate::str::contains("Verified 2 files"));
+
+    std::fs::write(&ffmpeg, "tampered").unwrap();
+    std::fs::remove_file(&ffprobe).unwrap();
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("verify")
+        .arg("ffmpeg")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(format!(
+            "MODIFIED {}",
+            ffmpeg.display()
+        )))
+        .stdout(predicate::str::contains(format!(
+            "MISSING  {}",
+            ffprobe.display()
+        )))
+        .stderr(predicate::str::contains(
+            "Verification failed for 2 of 2 files",
+        ));
+}
+
+#[test]
+fn test_verify_unknown_name() {
+    let home = tempfile::tempdir().unwrap();
+    let mut cmd = bin();
+    cmd.env("HOME", home.path())
+        .arg("--ansi=false")
+        .arg("verify")
+        .arg("typos")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No receipt found for typos"));
+}